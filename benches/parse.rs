@@ -0,0 +1,51 @@
+//! Parsing benchmark against the larger fixture, run with `cargo bench`
+//!
+//! Pass a path to benchmark a different (e.g. representative firmware) binary.
+
+use std::time::{Duration, Instant};
+
+use whatthestack::*;
+
+/// Number of timed iterations per configuration
+const ITERATIONS: u32 = 10;
+
+/// Median parse time over the timed iterations
+fn time(data: &[u8], opts: &ParseOptions) -> Duration {
+    let mut times: Vec<_> = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(Report::parse_bytes_with(data, opts).unwrap());
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[times.len() / 2]
+}
+
+fn main() {
+    let file = std::env::args()
+        .skip(1)
+        .find(|a| !a.starts_with('-'))
+        .unwrap_or_else(|| format!("{}/tests/fixtures/many.elf", env!("CARGO_MANIFEST_DIR")));
+    let data = std::fs::read(&file).unwrap();
+
+    let functions = Report::parse_bytes_with(&data, &ParseOptions::new())
+        .unwrap()
+        .functions
+        .len();
+    println!("{}: {} functions", file, functions);
+
+    for map_source in [false, true] {
+        for parallel in [false, true] {
+            let opts = ParseOptions::new()
+                .map_source(map_source)
+                .parallel(parallel);
+            println!(
+                "map_source: {:<5} parallel: {:<5} {:?}",
+                map_source,
+                parallel,
+                time(&data, &opts)
+            );
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use addr2line::object::{
+    Architecture, Object, ObjectSection, ObjectSymbol, Relocation, RelocationKind,
+    RelocationTarget, SectionIndex, SymbolKind,
+};
+use log::debug;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Function, NameTransform, Report};
+
+/// Load caller -> callee (mangled) symbol names from relocations
+///
+/// This requires relocation information, available for object files
+/// or executables linked with `--emit-relocs`, otherwise the returned
+/// map will be empty.
+pub fn load_calls(
+    d: &[u8],
+    arch: Architecture,
+) -> Result<HashMap<String, BTreeSet<String>>, anyhow::Error> {
+    let object = addr2line::object::File::parse(d)?;
+
+    // Thumb function symbols have the low address bit set
+    let mask = match arch {
+        Architecture::Arm => !1,
+        _ => !0,
+    };
+
+    // Collect function symbols by section, ordered by address
+    let mut symbols: HashMap<SectionIndex, BTreeMap<u64, (u64, &str)>> = HashMap::new();
+    for s in object.symbols() {
+        if let (SymbolKind::Text, Some(idx), Ok(name)) = (s.kind(), s.section_index(), s.name()) {
+            if name.is_empty() {
+                continue;
+            }
+            symbols
+                .entry(idx)
+                .or_default()
+                .insert(s.address() & mask, (s.size(), name));
+        }
+    }
+
+    // Find the function covering an address in a section
+    let lookup = |idx: SectionIndex, addr: u64| -> Option<&str> {
+        let (start, (size, name)) = symbols.get(&idx)?.range(..=addr).next_back()?;
+        match addr < start + (*size).max(1) {
+            true => Some(*name),
+            false => None,
+        }
+    };
+
+    // Find the function covering a section-relative relocation target
+    let lookup_section = |idx: SectionIndex, r: &Relocation| -> Option<&str> {
+        let base = object.section_by_index(idx).ok()?.address();
+
+        // PC-relative addends are biased by the relocation field size
+        let bias = match r.kind() {
+            RelocationKind::Relative | RelocationKind::PltRelative => r.size() as i64 / 8,
+            _ => 0,
+        };
+
+        lookup(idx, base + (r.addend() + bias).max(0) as u64)
+    };
+
+    let mut calls: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for section in object.sections() {
+        for (offset, r) in section.relocations() {
+            // Resolve calling function
+            let caller = match lookup(section.index(), offset) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            // Resolve called function
+            let callee = match r.target() {
+                RelocationTarget::Symbol(idx) => match object.symbol_by_index(idx) {
+                    Ok(s) if s.kind() == SymbolKind::Section => {
+                        s.section_index().and_then(|i| lookup_section(i, &r))
+                    }
+                    Ok(s) if s.kind() == SymbolKind::Text || s.is_undefined() => s.name().ok(),
+                    _ => None,
+                },
+                RelocationTarget::Section(idx) => lookup_section(idx, &r),
+                _ => None,
+            };
+
+            if let Some(callee) = callee.filter(|c| !c.is_empty()) {
+                calls
+                    .entry(caller.to_string())
+                    .or_default()
+                    .insert(callee.to_string());
+            }
+        }
+    }
+
+    debug!("Loaded calls for {} functions", calls.len());
+
+    Ok(calls)
+}
+
+/// Frame in a call chain
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Frame {
+    /// Function for this frame
+    pub function: Function,
+
+    /// Cumulative stack from the root to (and including) this frame
+    pub cumulative: u64,
+}
+
+/// Call graph over report functions, keyed by name
+pub struct CallGraph<'a> {
+    functions: HashMap<&'a str, &'a Function>,
+}
+
+impl<'a> CallGraph<'a> {
+    /// Build a call graph from a report
+    pub fn new(report: &'a Report) -> Self {
+        let functions = report
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), f))
+            .collect();
+
+        Self { functions }
+    }
+
+    /// Fetch a function by name
+    pub fn get(&self, name: &str) -> Option<&'a Function> {
+        self.functions.get(name).copied()
+    }
+
+    /// Compute the set of functions transitively reachable from a root function
+    pub fn reachable(&self, root: &str) -> Option<HashSet<&'a str>> {
+        let root = self.get(root)?;
+
+        let mut seen = HashSet::new();
+        let mut pending = vec![root];
+        while let Some(f) = pending.pop() {
+            if !seen.insert(f.name.as_str()) {
+                continue;
+            }
+            pending.extend(f.calls.iter().filter_map(|c| self.get(c)));
+        }
+
+        Some(seen)
+    }
+
+    /// Compute the worst case stack call chain from a root function
+    ///
+    /// Returns the frames along the deepest path and whether recursion
+    /// was detected (and skipped) while walking the graph.
+    pub fn worst_path(&self, root: &str) -> Option<(Vec<Frame>, bool)> {
+        let root = self.get(root)?;
+
+        let mut memo = HashMap::new();
+        let mut visiting = HashSet::new();
+        let mut recursive = false;
+        self.depth(root, &mut memo, &mut visiting, &mut recursive);
+
+        // Follow the heaviest callee from the root
+        let mut frames = vec![];
+        let mut cumulative = 0;
+        let mut seen = HashSet::new();
+        let mut next = Some(root);
+
+        while let Some(f) = next {
+            if !seen.insert(f.name.as_str()) {
+                break;
+            }
+
+            cumulative += f.stack;
+            frames.push(Frame {
+                function: f.clone(),
+                cumulative,
+            });
+
+            next = memo.get(f.name.as_str()).and_then(|(_, c)| *c);
+        }
+
+        Some((frames, recursive))
+    }
+
+    /// Compute worst case cumulative stack (including callees) for every function
+    ///
+    /// Returns depths by function name and whether recursion was detected, in
+    /// which case cycles are cut and depths may be underestimated.
+    pub fn max_depths(&self) -> (HashMap<&'a str, u64>, bool) {
+        let mut memo = HashMap::new();
+        let mut visiting = HashSet::new();
+        let mut recursive = false;
+
+        // Visit in name order for deterministic results where cycles are cut
+        let mut functions: Vec<_> = self.functions.values().collect();
+        functions.sort_by_key(|f| f.name.as_str());
+
+        for f in functions {
+            self.depth(f, &mut memo, &mut visiting, &mut recursive);
+        }
+
+        let depths = memo.into_iter().map(|(n, (d, _))| (n, d)).collect();
+        (depths, recursive)
+    }
+
+    /// Render the call graph as a Graphviz digraph
+    ///
+    /// Nodes are labelled with name and stack size, edges along the provided
+    /// (e.g. worst case) path are highlighted with cumulative stack.
+    pub fn dot(&self, transform: &dyn NameTransform, path: &[Frame]) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+
+        // Order nodes by name for stable output
+        let mut functions: Vec<_> = self.functions.values().collect();
+        functions.sort_by_key(|f| f.name.as_str());
+
+        let mut s = String::from("digraph calls {\n    node [shape=box];\n");
+
+        for f in &functions {
+            s += &format!(
+                "    \"{}\" [label=\"{}\\n{} bytes\"];\n",
+                escape(&f.name),
+                escape(&f.display_name(transform)),
+                f.stack
+            );
+        }
+
+        for f in &functions {
+            for c in f
+                .calls
+                .iter()
+                .filter(|c| self.functions.contains_key(c.as_str()))
+            {
+                // Highlight edges along the path
+                let on_path = path
+                    .windows(2)
+                    .find(|w| w[0].function.name == f.name && &w[1].function.name == c);
+
+                match on_path {
+                    Some(w) => {
+                        s += &format!(
+                            "    \"{}\" -> \"{}\" [color=red, penwidth=2, label=\"{}\"];\n",
+                            escape(&f.name),
+                            escape(c),
+                            w[1].cumulative
+                        )
+                    }
+                    None => s += &format!("    \"{}\" -> \"{}\";\n", escape(&f.name), escape(c)),
+                }
+            }
+        }
+
+        s += "}\n";
+        s
+    }
+
+    /// Compute worst case depth for a function, memoising the heaviest callee
+    fn depth(
+        &self,
+        f: &'a Function,
+        memo: &mut HashMap<&'a str, (u64, Option<&'a Function>)>,
+        visiting: &mut HashSet<&'a str>,
+        recursive: &mut bool,
+    ) -> u64 {
+        if let Some((d, _)) = memo.get(f.name.as_str()) {
+            return *d;
+        }
+
+        // Cycles contribute nothing, but are flagged
+        if !visiting.insert(f.name.as_str()) {
+            debug!("Recursion detected at {}", f.name);
+            *recursive = true;
+            return 0;
+        }
+
+        let mut worst: Option<(u64, &'a Function)> = None;
+        for c in f.calls.iter().filter_map(|c| self.get(c)) {
+            let d = self.depth(c, memo, visiting, recursive);
+            if worst.map(|(w, _)| d > w).unwrap_or(true) {
+                worst = Some((d, c));
+            }
+        }
+
+        visiting.remove(f.name.as_str());
+
+        let d = f.stack + worst.map(|(d, _)| d).unwrap_or(0);
+        memo.insert(f.name.as_str(), (d, worst.map(|(_, c)| c)));
+
+        d
+    }
+}
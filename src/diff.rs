@@ -0,0 +1,315 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::{percentile, Function, Report};
+
+/// Change in a single function between two reports
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionDiff {
+    /// Full / demangled name
+    pub name: String,
+
+    /// Function in the previous report (None if added)
+    pub prev: Option<Function>,
+
+    /// Function in the current report (None if removed)
+    pub current: Option<Function>,
+}
+
+impl FunctionDiff {
+    /// Typed text size change
+    pub fn text(&self) -> StackDelta {
+        StackDelta {
+            old: self.prev.as_ref().map(|f| f.text),
+            new: self.current.as_ref().map(|f| f.text),
+        }
+    }
+
+    /// Typed stack size change
+    pub fn stack(&self) -> StackDelta {
+        StackDelta {
+            old: self.prev.as_ref().map(|f| f.stack),
+            new: self.current.as_ref().map(|f| f.stack),
+        }
+    }
+
+    /// Text size change, missing functions count as zero
+    pub fn text_delta(&self) -> i64 {
+        self.text().value()
+    }
+
+    /// Stack size change, missing functions count as zero
+    pub fn stack_delta(&self) -> i64 {
+        self.stack().value()
+    }
+}
+
+/// Kind of change between reports
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum DeltaKind {
+    /// Present only in the current report
+    Added,
+    /// Present only in the previous report
+    Removed,
+    /// Present in both with a different value
+    Changed,
+    /// Present in both with the same value
+    Unchanged,
+}
+
+/// Size change between reports, with missing values kept distinct from zero
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StackDelta {
+    /// Value in the previous report
+    pub old: Option<u64>,
+
+    /// Value in the current report
+    pub new: Option<u64>,
+}
+
+impl StackDelta {
+    /// Classify the change
+    pub fn kind(&self) -> DeltaKind {
+        match (self.old, self.new) {
+            (None, _) => DeltaKind::Added,
+            (_, None) => DeltaKind::Removed,
+            (Some(o), Some(n)) if o == n => DeltaKind::Unchanged,
+            _ => DeltaKind::Changed,
+        }
+    }
+
+    /// Signed change where present in both reports, saturating at the bounds of `i64`
+    pub fn delta(&self) -> Option<i64> {
+        match (self.old, self.new) {
+            (Some(o), Some(n)) => Some(signed_delta(o, n)),
+            _ => None,
+        }
+    }
+
+    /// Signed change with missing values counted as zero
+    pub fn value(&self) -> i64 {
+        signed_delta(self.old.unwrap_or(0), self.new.unwrap_or(0))
+    }
+}
+
+/// Compute `new - old` without overflow
+fn signed_delta(old: u64, new: u64) -> i64 {
+    (new as i128 - old as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Function with text and stack changes between reports
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FunctionChange {
+    /// Current function, or previous function where removed
+    pub function: Function,
+
+    /// Text size change
+    pub text: StackDelta,
+
+    /// Stack size change
+    pub stack: StackDelta,
+}
+
+impl FunctionChange {
+    /// Classify the change, functions changed in either text or stack size are changed
+    pub fn kind(&self) -> DeltaKind {
+        match (self.text.kind(), self.stack.kind()) {
+            (DeltaKind::Unchanged, k) | (k, DeltaKind::Unchanged) => k,
+            (k, _) => k,
+        }
+    }
+}
+
+impl From<&FunctionDiff> for FunctionChange {
+    fn from(d: &FunctionDiff) -> Self {
+        Self {
+            function: d
+                .current
+                .as_ref()
+                .or(d.prev.as_ref())
+                .cloned()
+                .unwrap_or_default(),
+            text: d.text(),
+            stack: d.stack(),
+        }
+    }
+}
+
+/// Structured comparison between two reports, matched by function name
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diff {
+    /// Functions present only in the current report
+    pub added: Vec<FunctionChange>,
+
+    /// Functions present only in the previous report
+    pub removed: Vec<FunctionChange>,
+
+    /// Functions present in both with a different text or stack size
+    pub changed: Vec<FunctionChange>,
+}
+
+impl Diff {
+    /// Build a comparison from per-function diffs, dropping unchanged functions
+    pub fn new(diffs: &[FunctionDiff]) -> Self {
+        let mut diff = Diff::default();
+
+        for d in diffs {
+            match (d.prev.is_some(), d.current.is_some()) {
+                (false, _) => diff.added.push(d.into()),
+                (_, false) => diff.removed.push(d.into()),
+                _ if d.text().kind() == DeltaKind::Changed
+                    || d.stack().kind() == DeltaKind::Changed =>
+                {
+                    diff.changed.push(d.into())
+                }
+                _ => (),
+            }
+        }
+
+        diff
+    }
+
+    /// Functions with increased stack (and optionally text) size, including added functions
+    ///
+    /// Removed functions are never counted as regressions, nor are functions with
+    /// increased text where the stack shrank.
+    pub fn regressions(&self, include_text: bool) -> Vec<&FunctionChange> {
+        self.added
+            .iter()
+            .chain(&self.changed)
+            .filter(|c| match c.stack.value() {
+                s if s > 0 => true,
+                0 => include_text && c.text.value() > 0,
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Changes for a list of functions, in order, with unchanged functions included
+    ///
+    /// Functions are matched by name and address so duplicate names keep their own changes,
+    /// removed functions from the previous report match their removal.
+    pub fn changes_for(&self, functions: &[Function]) -> Vec<FunctionChange> {
+        let index: HashMap<_, _> = self
+            .added
+            .iter()
+            .chain(&self.changed)
+            .chain(&self.removed)
+            .map(|c| ((c.function.name.as_str(), c.function.addr), c))
+            .collect();
+
+        functions
+            .iter()
+            .map(|f| match index.get(&(f.name.as_str(), f.addr)) {
+                Some(c) => (*c).clone(),
+                None => {
+                    let same = |v| StackDelta {
+                        old: Some(v),
+                        new: Some(v),
+                    };
+                    FunctionChange {
+                        function: f.clone(),
+                        text: same(f.text),
+                        stack: same(f.stack),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Check whether the reports are equivalent
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Aggregate distribution shift between two reports
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffSummary {
+    /// Total stack change across all functions
+    pub total_stack_delta: i64,
+
+    /// Number of functions with increased stack use
+    pub grew: usize,
+
+    /// Number of functions with decreased stack use
+    pub shrank: usize,
+
+    /// Function with the largest stack increase
+    pub largest_regression: Option<(String, i64)>,
+
+    /// p50 / p90 / p99 stack in the previous report
+    pub prev_percentiles: [u64; 3],
+
+    /// p50 / p90 / p99 stack in the current report
+    pub current_percentiles: [u64; 3],
+}
+
+impl DiffSummary {
+    /// Compute a summary from a set of function diffs
+    pub fn new(diffs: &[FunctionDiff]) -> Self {
+        let changes: Vec<_> = diffs.iter().map(FunctionChange::from).collect();
+        let stacks = |f: fn(&FunctionDiff) -> Option<&Function>| {
+            diffs.iter().filter_map(f).map(|f| f.stack).collect()
+        };
+
+        Self::build(
+            &changes,
+            stacks(|d| d.prev.as_ref()),
+            stacks(|d| d.current.as_ref()),
+        )
+    }
+
+    /// Compute a summary from a structured comparison of two reports
+    pub fn from_diff(diff: &Diff, current: &Report, prev: &Report) -> Self {
+        let changes: Vec<_> = diff
+            .added
+            .iter()
+            .chain(&diff.removed)
+            .chain(&diff.changed)
+            .cloned()
+            .collect();
+        let stacks = |r: &Report| r.functions.iter().map(|f| f.stack).collect();
+
+        Self::build(&changes, stacks(prev), stacks(current))
+    }
+
+    /// Summarise changes alongside the stack sizes of each report
+    fn build(changes: &[FunctionChange], mut prev: Vec<u64>, mut current: Vec<u64>) -> Self {
+        prev.sort();
+        current.sort();
+
+        let largest_regression = changes
+            .iter()
+            .filter(|c| c.stack.value() > 0)
+            .max_by_key(|c| c.stack.value())
+            .map(|c| (c.function.name.clone(), c.stack.value()));
+
+        let p = |v: &[u64]| {
+            [
+                percentile(v, 50.0),
+                percentile(v, 90.0),
+                percentile(v, 99.0),
+            ]
+        };
+
+        Self {
+            total_stack_delta: changes.iter().map(|c| c.stack.value()).sum(),
+            grew: changes.iter().filter(|c| c.stack.value() > 0).count(),
+            shrank: changes.iter().filter(|c| c.stack.value() < 0).count(),
+            largest_regression,
+            prev_percentiles: p(&prev),
+            current_percentiles: p(&current),
+        }
+    }
+}
@@ -1,23 +1,69 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
 use addr2line::{
-    object::{Object, ObjectSection, SymbolMap, SymbolMapName},
+    gimli,
+    object::{Architecture, CompressionFormat, Object, ObjectSection, SymbolMap, SymbolMapName},
     ObjectContext,
 };
 
-use log::debug;
+use log::{debug, log, warn, Level};
+
+/// Check a separate debug file matches an executable by GNU build-id, warning on mismatch
+pub fn check_build_id(d: &[u8], debug: &[u8]) -> anyhow::Result<()> {
+    let exe = addr2line::object::File::parse(d)?.build_id()?;
+    let dbg = addr2line::object::File::parse(debug)?.build_id()?;
+
+    match (exe, dbg) {
+        (Some(a), Some(b)) if a != b => {
+            warn!(
+                "Debug file build-id does not match executable, source locations may be incorrect"
+            )
+        }
+        (Some(_), Some(_)) => debug!("Debug file build-id matched"),
+        _ => warn!("Missing build-id, unable to check debug file matches executable"),
+    }
+
+    Ok(())
+}
 
 pub struct DwarfContext<'a> {
     symbols: SymbolMap<SymbolMapName<'a>>,
     context: ObjectContext,
+    mask: u64,
 }
 
 impl<'a> DwarfContext<'a> {
     pub fn load(d: &'a [u8]) -> anyhow::Result<Self> {
+        Self::load_with(d, Level::Warn)
+    }
+
+    /// Load a further context for data already loaded with [`DwarfContext::load`], logging
+    /// problems at debug level rather than repeating warnings
+    pub fn load_quiet(d: &'a [u8]) -> anyhow::Result<Self> {
+        Self::load_with(d, Level::Debug)
+    }
+
+    fn load_with(d: &'a [u8], level: Level) -> anyhow::Result<Self> {
         // Parse file
         let object = addr2line::object::File::parse(d)?;
 
         debug!("Sections:");
         for s in object.sections() {
             debug!("{}, {}, {}", s.index().0, s.address(), s.name().unwrap());
+
+            // Check compressed debug sections (SHF_COMPRESSED or `.zdebug_*`) use a supported
+            // format, addr2line otherwise silently treats these as empty
+            let name = s.name().unwrap_or_default();
+            if !name.starts_with(".debug_") && !name.starts_with(".zdebug_") {
+                continue;
+            }
+            match s.compressed_file_range().map(|c| c.format) {
+                Ok(CompressionFormat::Unknown) => {
+                    log!(level, "Unsupported compression for debug section {}", name);
+                }
+                Ok(CompressionFormat::None) | Err(_) => (),
+                Ok(f) => debug!("Compressed debug section {} ({:?})", name, f),
+            }
         }
 
         let symbols = object.symbol_map();
@@ -25,35 +71,179 @@ impl<'a> DwarfContext<'a> {
         // Parse dwarf
         let context = addr2line::Context::new(&object)?;
 
-        context.parse_lines()?;
-        context.parse_functions()?;
+        // Pre-parse line and function info, on failure the remaining units
+        // are parsed lazily so one malformed unit doesn't prevent lookups
+        if let Err(e) = context.parse_lines() {
+            log!(level, "Failed to parse DWARF line info: {}", e);
+        }
+        if let Err(e) = context.parse_functions() {
+            log!(level, "Failed to parse DWARF function info: {}", e);
+        }
+
+        // Thumb function symbols have the low address bit set
+        let mask = match object.architecture() {
+            Architecture::Arm => !1,
+            _ => !0,
+        };
 
-        Ok(Self { symbols, context })
+        Ok(Self {
+            symbols,
+            context,
+            mask,
+        })
     }
 
-    pub fn get_line(&self, name: &str, addr: u64) -> anyhow::Result<Option<String>> {
+    /// Resolve the source location (`file:line`) for a function
+    ///
+    /// This masks the Thumb bit from addresses, falling back to locations within
+    /// the function range, then to the start of the containing symbol.
+    pub fn get_line(&self, name: &str, addr: u64, size: u64) -> anyhow::Result<Option<String>> {
         debug!("Lookup sym: {} addr: 0x{:08x}", name, addr);
 
-        // Find symbol by name / addr
-        let s = self.symbols.get(addr);
-        debug!("Match symbol: {:?}", s);
+        let addr = addr & self.mask;
+
+        // Find location via dwarf at the function address
+        let mut r = self.context.find_location(addr)?;
+
+        // Fall back to the first location within the function range
+        if r.is_none() && size > 0 {
+            r = self
+                .context
+                .find_location_range(addr, addr + size)?
+                .find(|(_, _, l)| l.file.is_some() && l.line.is_some())
+                .map(|(_, _, l)| l);
+        }
+
+        // Fall back to the containing symbol
+        if r.is_none() {
+            let s = self.symbols.get(addr);
+            debug!("Match symbol: {:?}", s);
+
+            if let Some(s) = s.filter(|s| s.address() & self.mask != addr) {
+                r = self.context.find_location(s.address() & self.mask)?;
+            }
+        }
 
-        // Find location via dwarf
-        // TODO: this, doesn't seem to work, maybe dwarf is missing line info?
-        let r = match self.context.find_location(addr)? {
-            Some(r) => r,
-            None => {
+        // Format source location, optimised builds may be missing file or line
+        let s = match r.map(|r| (r.file, r.line)) {
+            Some((Some(f), Some(l))) => format!("{}:{}", f, l),
+            Some((Some(f), None)) => format!("{}:?", f),
+            Some((None, Some(l))) => format!("?:{}", l),
+            _ => {
                 debug!("No line info for {}", name);
                 return Ok(None);
             }
         };
 
-        // Format source location
-        let s = match (r.file, r.line) {
-            (Some(f), Some(l)) => format!("{}:{}", f, l),
-            _ => unimplemented!(),
+        Ok(Some(s))
+    }
+
+    /// Resolve the inlined call chain at an address as `file:line (function)`, innermost first
+    pub fn inline_frames(&self, addr: u64) -> anyhow::Result<Vec<String>> {
+        let mut frames = vec![];
+
+        let mut iter = self.context.find_frames(addr & self.mask)?;
+        while let Some(f) = iter.next()? {
+            let location = match f.location.map(|l| (l.file, l.line)) {
+                Some((Some(f), Some(l))) => format!("{}:{}", f, l),
+                Some((Some(f), None)) => format!("{}:?", f),
+                Some((None, Some(l))) => format!("?:{}", l),
+                _ => "?".to_string(),
+            };
+            let function = match f.function.as_ref().map(|n| n.demangle()) {
+                Some(Ok(n)) => n.to_string(),
+                _ => "?".to_string(),
+            };
+
+            frames.push(format!("{} ({})", location, function));
+        }
+
+        Ok(frames)
+    }
+}
+
+/// Load function sizes (`DW_AT_high_pc - DW_AT_low_pc`) by address from DWARF subprograms
+pub fn load_function_sizes(d: &[u8]) -> anyhow::Result<BTreeMap<u64, u64>> {
+    let object = addr2line::object::File::parse(d)?;
+    let endian = match object.is_little_endian() {
+        true => gimli::RunTimeEndian::Little,
+        false => gimli::RunTimeEndian::Big,
+    };
+
+    // Load (and decompress) DWARF sections
+    let load = |id: gimli::SectionId| -> Result<Cow<[u8]>, gimli::Error> {
+        Ok(object
+            .section_by_name(id.name())
+            .and_then(|s| s.uncompressed_data().ok())
+            .unwrap_or(Cow::Borrowed(&[])))
+    };
+    let dwarf = gimli::Dwarf::load(load)?;
+    let dwarf = dwarf.borrow(|s| gimli::EndianSlice::new(s, endian));
+
+    // Skip malformed units rather than failing entirely
+    let mut sizes = BTreeMap::new();
+    let mut units = dwarf.units();
+    loop {
+        let header = match units.next() {
+            Ok(Some(h)) => h,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Failed to read DWARF unit header: {}", e);
+                break;
+            }
+        };
+
+        let unit = match dwarf.unit(header) {
+            Ok(u) => u,
+            Err(e) => {
+                warn!("Failed to parse DWARF unit: {}", e);
+                continue;
+            }
         };
 
-        Ok(Some(s))
+        if let Err(e) = load_unit_function_sizes(&dwarf, &unit, &mut sizes) {
+            warn!("Failed to load DWARF function sizes for unit: {}", e);
+        }
+    }
+
+    debug!("Loaded {} DWARF function sizes", sizes.len());
+
+    Ok(sizes)
+}
+
+/// Load function sizes from the subprograms in a DWARF unit, stopping at the first error
+fn load_unit_function_sizes<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    sizes: &mut BTreeMap<u64, u64>,
+) -> Result<(), gimli::Error> {
+    let mut entries = unit.entries();
+    while let Some((_, e)) = entries.next_dfs()? {
+        if e.tag() != gimli::DW_TAG_subprogram {
+            continue;
+        }
+
+        let low = match e.attr_value(gimli::DW_AT_low_pc)? {
+            Some(gimli::AttributeValue::Addr(a)) => a,
+            Some(gimli::AttributeValue::DebugAddrIndex(i)) => dwarf.address(unit, i)?,
+            _ => continue,
+        };
+
+        // High PC may be an address or an offset from low PC
+        let size = match e.attr_value(gimli::DW_AT_high_pc)? {
+            Some(gimli::AttributeValue::Addr(a)) => a.saturating_sub(low),
+            Some(gimli::AttributeValue::DebugAddrIndex(i)) => {
+                dwarf.address(unit, i)?.saturating_sub(low)
+            }
+            Some(v) => match v.udata_value() {
+                Some(n) => n,
+                None => continue,
+            },
+            None => continue,
+        };
+
+        sizes.insert(low, size);
     }
+
+    Ok(())
 }
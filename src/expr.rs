@@ -0,0 +1,157 @@
+use std::str::FromStr;
+
+use crate::Function;
+
+/// Function fields available in sort expressions
+const FIELDS: &[&str] = &["stack", "text", "addr", "ratio"];
+
+/// Numeric expression over function fields, for custom sorting (e.g. `stack * 2 + text`)
+#[derive(Clone, Debug, PartialEq)]
+pub enum SortExpr {
+    /// Numeric literal
+    Value(f64),
+    /// Function field (see `FIELDS`)
+    Field(String),
+    /// Binary operation (`+`, `-`, `*`, `/`)
+    Op(char, Box<SortExpr>, Box<SortExpr>),
+    /// Negation
+    Neg(Box<SortExpr>),
+}
+
+impl SortExpr {
+    /// Evaluate the expression for a function
+    pub fn eval(&self, f: &Function) -> f64 {
+        match self {
+            SortExpr::Value(v) => *v,
+            SortExpr::Field(n) => match n.as_str() {
+                "stack" => f.stack as f64,
+                "text" => f.text as f64,
+                "addr" => f.addr as f64,
+                "ratio" => f.ratio(),
+                _ => unreachable!("fields are validated when parsing"),
+            },
+            SortExpr::Op(op, a, b) => {
+                let (a, b) = (a.eval(f), b.eval(f));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => a / b,
+                }
+            }
+            SortExpr::Neg(a) => -a.eval(f),
+        }
+    }
+}
+
+impl FromStr for SortExpr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut p = Parser { tokens, pos: 0 };
+
+        let e = p.sum()?;
+        match p.tokens.get(p.pos) {
+            None => Ok(e),
+            Some(t) => Err(anyhow::anyhow!("unexpected '{}' in sort expression", t)),
+        }
+    }
+}
+
+/// Split an expression into numbers, identifiers and operators
+fn tokenize(s: &str) -> Result<Vec<String>, anyhow::Error> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '+' | '-' | '*' | '/' | '(' | ')' => tokens.push(c.to_string()),
+            c if c.is_alphanumeric() || c == '.' || c == '_' => {
+                let mut t = c.to_string();
+                while let Some(n) = chars.next_if(|n| n.is_alphanumeric() || *n == '.' || *n == '_')
+                {
+                    t.push(n);
+                }
+                tokens.push(t);
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "invalid character '{}' in sort expression",
+                    c
+                ))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser over expression tokens
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Consume the next token if it is one of the provided operators
+    fn op(&mut self, ops: &[&str]) -> Option<char> {
+        let t = self.tokens.get(self.pos)?;
+        if !ops.contains(&t.as_str()) {
+            return None;
+        }
+        self.pos += 1;
+        t.chars().next()
+    }
+
+    /// Parse `product (('+' | '-') product)*`
+    fn sum(&mut self) -> Result<SortExpr, anyhow::Error> {
+        let mut e = self.product()?;
+        while let Some(op) = self.op(&["+", "-"]) {
+            e = SortExpr::Op(op, Box::new(e), Box::new(self.product()?));
+        }
+        Ok(e)
+    }
+
+    /// Parse `unary (('*' | '/') unary)*`
+    fn product(&mut self) -> Result<SortExpr, anyhow::Error> {
+        let mut e = self.unary()?;
+        while let Some(op) = self.op(&["*", "/"]) {
+            e = SortExpr::Op(op, Box::new(e), Box::new(self.unary()?));
+        }
+        Ok(e)
+    }
+
+    /// Parse `'-' unary | '(' sum ')' | number | field`
+    fn unary(&mut self) -> Result<SortExpr, anyhow::Error> {
+        if self.op(&["-"]).is_some() {
+            return Ok(SortExpr::Neg(Box::new(self.unary()?)));
+        }
+
+        let t = match self.tokens.get(self.pos) {
+            Some(t) => t.clone(),
+            None => return Err(anyhow::anyhow!("unexpected end of sort expression")),
+        };
+        self.pos += 1;
+
+        match t.as_str() {
+            "(" => {
+                let e = self.sum()?;
+                match self.op(&[")"]) {
+                    Some(_) => Ok(e),
+                    None => Err(anyhow::anyhow!("missing ')' in sort expression")),
+                }
+            }
+            t if FIELDS.contains(&t) => Ok(SortExpr::Field(t.to_string())),
+            t => match t.parse::<f64>() {
+                Ok(v) => Ok(SortExpr::Value(v)),
+                Err(_) => Err(anyhow::anyhow!(
+                    "unknown identifier '{}' in sort expression (expected one of: {})",
+                    t,
+                    FIELDS.join(", ")
+                )),
+            },
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use clap::ValueEnum;
+use regex::Regex;
+
+use crate::Function;
+
+/// Function size used for size filtering
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum SizeField {
+    /// Filter by stack size
+    #[default]
+    Stack,
+    /// Filter by text (function) size
+    Text,
+}
+
+/// Matching mode for `--filter` values
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum FilterMode {
+    /// Match names starting with the value
+    #[default]
+    Prefix,
+    /// Match names containing a regex match anywhere
+    Regex,
+}
+
+/// Composable function filter
+///
+/// Name prefixes and patterns are combined with OR semantics, while the
+/// name regex, size bounds and address range must all match (AND). Functions
+/// containing any exclusion are then dropped.
+#[derive(Clone, Debug, Default)]
+pub struct FilterSet {
+    /// Name prefixes, any of which may match
+    pub prefixes: Vec<String>,
+
+    /// Name patterns, any of which may match (alongside prefixes)
+    pub patterns: Vec<Regex>,
+
+    /// Name substrings, any of which excludes a function
+    pub excludes: Vec<String>,
+
+    /// Regex that names must match
+    pub regex: Option<Regex>,
+
+    /// Minimum stack size (inclusive)
+    pub min_stack: Option<u64>,
+
+    /// Maximum stack size (inclusive)
+    pub max_stack: Option<u64>,
+
+    /// Minimum text size (inclusive)
+    pub min_text: Option<u64>,
+
+    /// Maximum text size (inclusive)
+    pub max_text: Option<u64>,
+
+    /// Minimum address (inclusive)
+    pub min_addr: Option<u64>,
+
+    /// Maximum address (inclusive)
+    pub max_addr: Option<u64>,
+}
+
+impl FilterSet {
+    /// Create an empty filter set, matching all functions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a name prefix
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefixes.push(prefix.to_string());
+        self
+    }
+
+    /// Add a name exclusion substring
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.excludes.push(pattern.to_string());
+        self
+    }
+
+    /// Add a name pattern
+    pub fn pattern(mut self, pattern: &str) -> Result<Self, anyhow::Error> {
+        let r = Regex::new(pattern)
+            .map_err(|e| anyhow::anyhow!("invalid filter pattern '{}': {}", pattern, e))?;
+        self.patterns.push(r);
+        Ok(self)
+    }
+
+    /// Add a name prefix or pattern depending on filter mode
+    pub fn name(self, mode: FilterMode, value: &str) -> Result<Self, anyhow::Error> {
+        match mode {
+            FilterMode::Prefix => Ok(self.prefix(value)),
+            FilterMode::Regex => self.pattern(value),
+        }
+    }
+
+    /// Set the name regex
+    pub fn regex(mut self, regex: &str) -> Result<Self, anyhow::Error> {
+        let r = Regex::new(regex)
+            .map_err(|e| anyhow::anyhow!("invalid filter regex '{}': {}", regex, e))?;
+        self.regex = Some(r);
+        Ok(self)
+    }
+
+    /// Set the minimum stack size
+    pub fn min_stack(mut self, min: u64) -> Self {
+        self.min_stack = Some(min);
+        self
+    }
+
+    /// Set the maximum stack size
+    pub fn max_stack(mut self, max: u64) -> Self {
+        self.max_stack = Some(max);
+        self
+    }
+
+    /// Set the minimum text size
+    pub fn min_text(mut self, min: u64) -> Self {
+        self.min_text = Some(min);
+        self
+    }
+
+    /// Set the maximum text size
+    pub fn max_text(mut self, max: u64) -> Self {
+        self.max_text = Some(max);
+        self
+    }
+
+    /// Set the minimum address
+    pub fn min_addr(mut self, min: u64) -> Self {
+        self.min_addr = Some(min);
+        self
+    }
+
+    /// Set the maximum address
+    pub fn max_addr(mut self, max: u64) -> Self {
+        self.max_addr = Some(max);
+        self
+    }
+
+    /// Set the minimum size for the selected field
+    pub fn min_size(self, field: SizeField, min: u64) -> Self {
+        match field {
+            SizeField::Stack => self.min_stack(min),
+            SizeField::Text => self.min_text(min),
+        }
+    }
+
+    /// Set the maximum size for the selected field
+    pub fn max_size(self, field: SizeField, max: u64) -> Self {
+        match field {
+            SizeField::Stack => self.max_stack(max),
+            SizeField::Text => self.max_text(max),
+        }
+    }
+
+    /// Check whether a function matches the filter set
+    pub fn matches(&self, f: &Function) -> bool {
+        // Match any prefix or pattern
+        let unnamed = self.prefixes.is_empty() && self.patterns.is_empty();
+        let named = self.prefixes.iter().any(|p| f.name.starts_with(p))
+            || self.patterns.iter().any(|p| p.is_match(&f.name));
+        if !unnamed && !named {
+            return false;
+        }
+
+        // Match regex
+        if let Some(r) = &self.regex {
+            if !r.is_match(&f.name) {
+                return false;
+            }
+        }
+
+        // Drop exclusions
+        if self.excludes.iter().any(|e| f.name.contains(e.as_str())) {
+            return false;
+        }
+
+        // Match size bounds
+        if self.min_stack.map(|m| f.stack < m).unwrap_or(false) {
+            return false;
+        }
+        if self.max_stack.map(|m| f.stack > m).unwrap_or(false) {
+            return false;
+        }
+        if self.min_text.map(|m| f.text < m).unwrap_or(false) {
+            return false;
+        }
+        if self.max_text.map(|m| f.text > m).unwrap_or(false) {
+            return false;
+        }
+
+        // Match address range
+        if self.min_addr.map(|m| f.addr < m).unwrap_or(false) {
+            return false;
+        }
+        if self.max_addr.map(|m| f.addr > m).unwrap_or(false) {
+            return false;
+        }
+
+        true
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{DeltaKind, Function, FunctionChange, Report};
+
+/// CSV report row, additional columns (e.g. diffs) are ignored
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    addr: String,
+    text: u64,
+    stack: u64,
+    name: String,
+    #[serde(default)]
+    source: String,
+}
+
+/// Check whether report data should be parsed as CSV, by extension or content
+pub(crate) fn is_csv(file: &str, d: &[u8]) -> bool {
+    if file.to_lowercase().ends_with(".csv") {
+        return true;
+    }
+
+    // JSON reports start with an object
+    !d.iter()
+        .find(|c| !c.is_ascii_whitespace())
+        .map(|c| *c == b'{')
+        .unwrap_or(true)
+}
+
+/// Load a report from CSV data with a header row
+pub(crate) fn load_csv(d: &[u8]) -> Result<Report, anyhow::Error> {
+    let mut functions = vec![];
+
+    for r in csv::Reader::from_reader(d).deserialize() {
+        let r: CsvRow = r?;
+
+        // Addresses may be hex (as displayed) or decimal
+        let addr = match r.addr.strip_prefix("0x") {
+            Some(h) => u64::from_str_radix(h, 16)?,
+            None => r.addr.parse()?,
+        };
+
+        functions.push(Function {
+            addr,
+            name: r.name,
+            text: r.text,
+            stack: r.stack,
+            source: r.source,
+            ..Default::default()
+        });
+    }
+
+    Ok(Report {
+        functions,
+        ..Default::default()
+    })
+}
+
+/// CSV report row with changes against a previous report
+#[derive(Debug, Serialize)]
+struct CsvDiffRow {
+    addr: String,
+    text: u64,
+    stack: u64,
+    name: String,
+    source: String,
+    text_diff: i64,
+    stack_diff: i64,
+    change: DeltaKind,
+}
+
+/// Encode a report to CSV data with a header row, loadable with `load_csv`
+pub(crate) fn save_csv(r: &Report) -> Result<Vec<u8>, anyhow::Error> {
+    let mut w = csv::Writer::from_writer(vec![]);
+
+    for f in &r.functions {
+        w.serialize(CsvRow {
+            addr: format!("0x{:08x}", f.addr),
+            text: f.text,
+            stack: f.stack,
+            name: f.name.clone(),
+            source: f.source.clone(),
+        })?;
+    }
+
+    Ok(w.into_inner()?)
+}
+
+/// Encode function changes to CSV, with `text_diff`, `stack_diff` and `change` columns
+///
+/// Added functions count from zero and removed functions have zero current sizes,
+/// diff columns are ignored when loading.
+pub fn changes_to_csv(changes: &[FunctionChange]) -> Result<String, anyhow::Error> {
+    let mut w = csv::Writer::from_writer(vec![]);
+
+    for c in changes {
+        let f = &c.function;
+        w.serialize(CsvDiffRow {
+            addr: format!("0x{:08x}", f.addr),
+            text: c.text.new.unwrap_or(0),
+            stack: c.stack.new.unwrap_or(0),
+            name: f.name.clone(),
+            source: f.source.clone(),
+            text_diff: c.text.value(),
+            stack_diff: c.stack.value(),
+            change: c.kind(),
+        })?;
+    }
+
+    Ok(String::from_utf8(w.into_inner()?)?)
+}
@@ -1,22 +1,458 @@
+use addr2line::object::Architecture;
 use regex::Regex;
 
+use crate::UNKNOWN_CRATE;
+
 // Regexes for matching rust function names / prefixes
 lazy_static::lazy_static! {
-    static ref PREFIX: Regex = Regex::new(r"^([a-z0-9_:]+)(.*)").unwrap();
-    static ref NAMES: Regex = Regex::new(r"(?:[a-z0-9_]+::)+([A-Z][a-z0-9_A-Z]+)").unwrap();
+    static ref NAMES: Regex = Regex::new(r"\b(?:[a-z0-9_]+::)+([A-Z][a-z0-9_A-Z]+)").unwrap();
+    static ref HASH: Regex = Regex::new(r"::h[0-9a-f]{16}\b").unwrap();
+    static ref LLVM_SUFFIX: Regex = Regex::new(r"\.llvm\.\d+$").unwrap();
+    static ref CLOSURE: Regex = Regex::new(r"\{closure#\d+\}").unwrap();
+    static ref CLOSURES: Regex = Regex::new(r"(?:::\{\{closure\}\})+").unwrap();
+    static ref CLOSURE_PATH: Regex = Regex::new(r"\b(?:[a-z0-9_]+::)+([a-z0-9_]+::\{\{closure\}\})").unwrap();
+    static ref BINDER: Regex = Regex::new(r"for<[^<>]*> ").unwrap();
+    static ref LIFETIME: Regex = Regex::new(r"'(\w+)(')?(?:, | )?").unwrap();
+    static ref CONST_SUFFIX: Regex = Regex::new(r"\b(\d+)(?:usize|isize|u8|u16|u32|u64|u128|i8|i16|i32|i64|i128)\b").unwrap();
+}
+
+/// Prefixes of compiler builtin and runtime symbols, excluded by default
+///
+/// This is intentionally conservative, covering ARM EABI helpers, libgcc /
+/// compiler-builtins integer and float routines, and panic machinery.
+pub const RUNTIME_PREFIXES: &[&str] = &[
+    "__aeabi_",
+    "__udiv",
+    "__umod",
+    "__div",
+    "__mod",
+    "__mul",
+    "compiler_builtins::",
+    "core::panicking::",
+    "std::panicking::",
+];
+
+/// Exact names of C runtime memory / string helpers, excluded by default
+pub const RUNTIME_NAMES: &[&str] = &["memcpy", "memmove", "memset", "memcmp", "bcmp", "strlen"];
+
+/// Helper to detect compiler builtin and runtime symbols
+pub fn is_runtime(name: &str) -> bool {
+    RUNTIME_NAMES.contains(&name) || RUNTIME_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+/// Transformation applied to function names for display
+pub trait NameTransform {
+    /// Transform a full / demangled function name
+    fn transform(&self, name: &str) -> String;
+}
+
+/// Default transform, shortens names via [`compress_name`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompressNames;
+
+impl NameTransform for CompressNames {
+    fn transform(&self, name: &str) -> String {
+        compress_name(name)
+    }
+}
+
+/// Identity transform, leaves names unchanged
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LongNames;
+
+impl NameTransform for LongNames {
+    fn transform(&self, name: &str) -> String {
+        name.to_string()
+    }
+}
+
+/// Allow closures to be used as transforms
+impl<F: Fn(&str) -> String> NameTransform for F {
+    fn transform(&self, name: &str) -> String {
+        (self)(name)
+    }
 }
 
 /// Helper to compress function names
+///
+/// Generic parameters are dropped, trait impls (`<X as Y>::method`) collapse to
+/// `X::method`, module paths are removed from types and closures are shortened to
+/// the enclosing function with a single `::{{closure}}` suffix.
 pub fn compress_name(n: &str) -> String {
-    // Strip obvious prefixes
-    let mut s = PREFIX.replace_all(n, "$2").to_string();
-    if s.len() == 0 {
-        return n.to_string();
-    }
+    // Drop generics and collapse qualified paths
+    let mut s = collapse_impls(&strip_generics(n));
+
+    // Normalise v0 closures and merge nested closures
+    s = CLOSURE.replace_all(&s, "{{closure}}").to_string();
+    s = CLOSURES.replace_all(&s, "::{{closure}}").to_string();
 
     // Shorten names
     s = NAMES.replace_all(&s, "$1").to_string();
+    s = CLOSURE_PATH.replace_all(&s, "$1").to_string();
 
     // Return compressed form
-    s.to_string()
+    s
+}
+
+/// Helper to collapse qualified paths (`<X as Y>` or `<X>`) to the self type `X`
+fn collapse_impls(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '<' {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        // Find the matching close bracket, ignoring `->` in fn types
+        let mut depth = 0;
+        let mut end = i;
+        while end < chars.len() {
+            match chars[end] {
+                '<' => depth += 1,
+                '>' if end > 0 && chars[end - 1] == '-' => (),
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => (),
+            }
+            end += 1;
+        }
+
+        // Keep the self type, dropping any trait
+        let inner: String = chars[i + 1..end.min(chars.len())].iter().collect();
+        let self_ty = match top_level_as(&inner) {
+            Some(idx) => &inner[..idx],
+            None => &inner,
+        };
+        out += &collapse_impls(self_ty);
+
+        i = end + 1;
+    }
+
+    out
+}
+
+/// Find the byte index of a top-level ` as ` in a qualified path
+fn top_level_as(s: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' | '[' | '(' => depth += 1,
+            '>' | ']' | ')' => depth -= 1,
+            _ if depth == 0 && s[i..].starts_with(" as ") => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Helper to compute a nearest-rank percentile over sorted values
+pub fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Helper to format a byte count with KiB / MiB / GiB units (one decimal place) above 1024
+pub fn human_size(v: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB"];
+
+    if v < 1024 {
+        return v.to_string();
+    }
+
+    let mut s = v as f64 / 1024.0;
+    let mut unit = 0;
+    while s >= 1024.0 && unit < UNITS.len() - 1 {
+        s /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", s, UNITS[unit])
+}
+
+/// Helper to truncate source paths in the middle, preserving the file name and line
+pub fn truncate_middle(s: &str, width: usize) -> String {
+    let len = s.chars().count();
+    if len <= width {
+        return s.to_string();
+    }
+
+    // Always keep the file name and line
+    let tail = match s.rfind('/') {
+        Some(i) => &s[i + 1..],
+        None => return s.to_string(),
+    };
+
+    // Fill remaining space with the start of the path
+    let keep = width.saturating_sub(tail.chars().count() + 2);
+    let head: String = s.chars().take(keep).collect();
+
+    format!("{}…/{}", head, tail)
+}
+
+/// Helper to extract the file path from a `file:line` source location
+pub fn source_file(source: &str) -> Option<&str> {
+    if source.is_empty() {
+        return None;
+    }
+
+    match source.rsplit_once(':') {
+        Some((f, _)) => Some(f),
+        None => Some(source),
+    }
+}
+
+/// Helper to extract the leading crate name from a demangled function name
+///
+/// Trait impls (`<foo::Bar as core::fmt::Debug>::fmt`) resolve to the implementing type's crate.
+pub fn crate_name(name: &str) -> Option<&str> {
+    let n = name.trim_start_matches(['<', '&', '*', ' ']);
+    let (c, _) = n.split_once("::")?;
+
+    match !c.is_empty() && c.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        true => Some(c),
+        false => None,
+    }
+}
+
+/// Helper to split a demangled function name into crate, module and function segments
+///
+/// Generics are dropped and trait impls resolve to the implementing type. The first
+/// segment is the crate as returned by [`crate_name`], or `<unknown>` where none is found.
+pub fn module_path(name: &str) -> Vec<String> {
+    let s = collapse_impls(&strip_generics(name));
+
+    let mut segments = vec![];
+    if crate_name(name).is_none() {
+        segments.push(UNKNOWN_CRATE.to_string());
+    }
+    segments.extend(s.split("::").map(|s| s.to_string()));
+
+    segments
+}
+
+/// Helper to parse an address or size, as decimal or `0x` prefixed hex
+pub fn parse_u64(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(h) => u64::from_str_radix(&h.replace('_', ""), 16).ok(),
+        None => s.replace('_', "").parse().ok(),
+    }
+}
+
+/// Helper to parse an architecture name (e.g. `arm`, `x86_64`, `riscv32`)
+pub fn parse_architecture(s: &str) -> Option<Architecture> {
+    let a = match s.to_lowercase().as_str() {
+        "aarch64" | "arm64" => Architecture::Aarch64,
+        "arm" | "thumb" => Architecture::Arm,
+        "avr" => Architecture::Avr,
+        "bpf" => Architecture::Bpf,
+        "i386" | "x86" => Architecture::I386,
+        "x86_64" | "amd64" => Architecture::X86_64,
+        "hexagon" => Architecture::Hexagon,
+        "loongarch64" => Architecture::LoongArch64,
+        "mips" => Architecture::Mips,
+        "mips64" => Architecture::Mips64,
+        "msp430" => Architecture::Msp430,
+        "powerpc" | "ppc" => Architecture::PowerPc,
+        "powerpc64" | "ppc64" => Architecture::PowerPc64,
+        "riscv32" => Architecture::Riscv32,
+        "riscv64" => Architecture::Riscv64,
+        "s390x" => Architecture::S390x,
+        "sparc64" => Architecture::Sparc64,
+        "wasm32" => Architecture::Wasm32,
+        "xtensa" => Architecture::Xtensa,
+        _ => return None,
+    };
+    Some(a)
+}
+
+/// Helper to strip generic parameters (`<...>` and turbofish `::<...>`) from names
+///
+/// Qualified paths such as `<T as Trait>::method` are preserved, with only
+/// generics within them removed.
+pub fn strip_generics(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+
+        // Generics follow an identifier or turbofish, otherwise `<` opens a qualified path
+        let prev = out.chars().last();
+        let is_generic = c == '<'
+            && (prev
+                .map(|p| p.is_alphanumeric() || p == '_')
+                .unwrap_or(false)
+                || out.ends_with("::"));
+
+        if !is_generic {
+            out.push(c);
+            i += 1;
+            continue;
+        }
+
+        // Remove turbofish separator
+        if out.ends_with("::") {
+            out.truncate(out.len() - 2);
+        }
+
+        // Skip to the matching close bracket, ignoring `->` in fn types
+        let mut depth = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '<' => depth += 1,
+                '>' if i > 0 && chars[i - 1] == '-' => (),
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => (),
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+
+    out
+}
+
+/// FNV-1a initial hash value
+pub(crate) const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// Update an FNV-1a hash, stable across platforms and toolchains
+pub(crate) fn fnv1a(mut h: u64, b: &[u8]) -> u64 {
+    for v in b {
+        h ^= *v as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+/// Score a fuzzy (case-insensitive subsequence) match of a query against a name
+///
+/// Returns None where the query is not a subsequence of the name, otherwise
+/// higher scores indicate better matches, favouring consecutive characters
+/// and matches at the start of path segments.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let mut score = 0;
+    let mut qi = 0;
+    let mut last: Option<usize> = None;
+
+    for (i, c) in name.iter().enumerate() {
+        if qi == query.len() {
+            break;
+        }
+        if c.to_lowercase().next() != Some(query[qi]) {
+            continue;
+        }
+
+        score += 16;
+
+        // Bonus for consecutive matches, penalty for gaps
+        match last {
+            Some(l) if l + 1 == i => score += 8,
+            Some(l) => score -= (i - l - 1).min(8) as i64,
+            None => score -= i.min(8) as i64,
+        }
+
+        // Bonus for matching the start of a segment
+        if i == 0 || matches!(name[i - 1], ':' | '<' | '_' | ' ') {
+            score += 8;
+        }
+
+        last = Some(i);
+        qi += 1;
+    }
+
+    match qi == query.len() {
+        true => Some(score),
+        false => None,
+    }
+}
+
+/// Stack use severity against warning / critical thresholds
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Normal,
+    Warn,
+    Crit,
+}
+
+/// Warning and critical stack thresholds, for highlighting
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Thresholds {
+    /// Stack size over which functions are flagged as warnings
+    pub warn: Option<u64>,
+
+    /// Stack size over which functions are flagged as critical
+    pub crit: Option<u64>,
+}
+
+impl Thresholds {
+    /// Classify a stack size against the thresholds
+    pub fn classify(&self, stack: u64) -> Severity {
+        match (self.warn, self.crit) {
+            (_, Some(c)) if stack > c => Severity::Crit,
+            (Some(w), _) if stack > w => Severity::Warn,
+            _ => Severity::Normal,
+        }
+    }
+}
+
+impl Severity {
+    /// Text marker for the severity (`!` / `!!`), for output without colour
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Severity::Normal => "",
+            Severity::Warn => "!",
+            Severity::Crit => "!!",
+        }
+    }
+}
+
+/// Helper to normalise demangled names for matching across builds
+///
+/// This strips `::h<hash>` disambiguators and LLVM `.llvm.<n>` suffixes, and
+/// replaces numbered (v0) closures with the legacy `{{closure}}` form.
+pub fn normalize_name(name: &str) -> String {
+    let s = HASH.replace_all(name, "");
+    let s = LLVM_SUFFIX.replace(&s, "");
+    CLOSURE.replace_all(&s, "{{closure}}").to_string()
+}
+
+/// Helper to simplify verbose demangled (v0) names
+///
+/// This drops higher-ranked binders (`for<'a>`) and lifetime parameters, and
+/// strips integer type suffixes from const generic values (`4usize` -> `4`).
+/// Char literals in const generics (`'x'`) are preserved.
+pub fn simplify_name(name: &str) -> String {
+    let s = BINDER.replace_all(name, "");
+
+    // Drop lifetimes, keeping char literals
+    let s = LIFETIME.replace_all(&s, |c: &regex::Captures| match c.get(2) {
+        Some(_) => c[0].to_string(),
+        None => "".to_string(),
+    });
+
+    // Remove generic lists left empty, and trailing separators
+    let s = s.replace("::<>", "").replace("<>", "").replace(", >", ">");
+
+    CONST_SUFFIX.replace_all(&s, "$1").to_string()
 }
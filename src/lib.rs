@@ -1,10 +1,19 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+pub use addr2line::object::Architecture;
+use addr2line::object::{BinaryFormat, Object, ObjectKind, ObjectSection, SectionKind};
 use clap::ValueEnum;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 
+use rayon::prelude::*;
 use rustc_demangle::demangle;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use stack_sizes::{analyze_executable, analyze_object};
 
 mod dwarf;
 pub use dwarf::*;
@@ -12,12 +21,46 @@ pub use dwarf::*;
 mod helpers;
 pub use helpers::*;
 
+mod diff;
+pub use diff::*;
+
+mod callgraph;
+pub use callgraph::*;
+
+mod symbols;
+pub use symbols::SymbolLookup;
+use symbols::*;
+
+mod filter;
+pub use filter::*;
+
+#[cfg(feature = "serde")]
+mod formats;
+#[cfg(feature = "serde")]
+pub use formats::changes_to_csv;
+#[cfg(feature = "serde")]
+use formats::*;
+
+mod expr;
+pub use expr::*;
+
+mod limits;
+pub use limits::*;
+
+mod stack_usage;
+pub use stack_usage::*;
+
+mod tasks;
+pub use tasks::*;
+
 #[derive(Clone, Debug, PartialEq, ValueEnum)]
 pub enum Mode {
     /// Load ELF file
     Elf,
-    /// Load Object File
+    /// Load Object File, addresses are section-relative offsets
     Object,
+    /// Load GCC `-fstack-usage` (`.su`) output, without addresses or text sizes
+    StackUsage,
 }
 
 #[derive(Clone, Debug, PartialEq, ValueEnum)]
@@ -28,22 +71,310 @@ pub enum Sort {
     Stack,
     /// Sort by function address
     Address,
+    /// Sort by stack to text size ratio
+    Ratio,
+    /// Sort by name
+    Name,
+}
+
+impl Sort {
+    /// Compare functions by this sort key, in ascending order
+    pub fn compare(&self, a: &Function, b: &Function) -> std::cmp::Ordering {
+        match self {
+            Sort::Text => a.text.cmp(&b.text),
+            Sort::Stack => a.stack.cmp(&b.stack),
+            Sort::Address => a.addr.cmp(&b.addr),
+            Sort::Ratio => a.ratio().total_cmp(&b.ratio()),
+            Sort::Name => a.name.cmp(&b.name),
+        }
+    }
+
+    /// Default order for this sort, descending for sizes and ascending otherwise
+    pub fn default_order(&self) -> Order {
+        match self {
+            Sort::Text | Sort::Stack | Sort::Ratio => Order::Desc,
+            Sort::Address | Sort::Name => Order::Asc,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum Order {
+    /// Smallest first
+    Asc,
+    /// Largest first
+    Desc,
+}
+
+impl Order {
+    /// Apply this order to an ascending comparison
+    pub fn apply(&self, o: std::cmp::Ordering) -> std::cmp::Ordering {
+        match self {
+            Order::Asc => o,
+            Order::Desc => o.reverse(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON report, loadable with `--prev`
+    Json,
+    /// CSV with `addr,text,stack,name,source` columns
+    Csv,
+    /// TOML report, loadable with `--prev`
+    Toml,
+    /// YAML report, loadable with `--prev`
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Infer output format from a file extension, defaulting to JSON
+    pub fn from_path(file: &str) -> Self {
+        let file = file.to_lowercase();
+        match file.rsplit_once('.').map(|(_, e)| e) {
+            Some("csv") => OutputFormat::Csv,
+            Some("toml") => OutputFormat::Toml,
+            Some("yaml") | Some("yml") => OutputFormat::Yaml,
+            _ => OutputFormat::Json,
+        }
+    }
+}
+
+/// Options for parsing reports
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    /// ELF or object file mode
+    pub mode: Mode,
+
+    /// Resolve addresses to source locations
+    pub map_source: bool,
+
+    /// Override the stack size section name
+    pub stack_section: Option<String>,
+
+    /// Log periodic progress during source resolution
+    pub progress: bool,
+
+    /// Process functions (source resolution, demangling, sections and calls) in parallel
+    pub parallel: bool,
+
+    /// Normalise function names (see [`normalize_name`])
+    pub normalize_names: bool,
+
+    /// Keep raw (mangled) symbol names in place of demangled names
+    pub raw_names: bool,
+
+    /// Merge functions sharing an address (see [`Report::dedup`])
+    pub dedup: bool,
+
+    /// Keep raw symbol addresses, including the Thumb bit on ARM targets
+    pub raw_addresses: bool,
+
+    /// Expected architecture, checked against the file header
+    ///
+    /// Where the header does not specify a known architecture this is used for
+    /// architecture-specific handling (Thumb addresses and call decoding) in place of
+    /// detection. It does not override pointer width or endianness, which are always
+    /// taken from the header, so headerless files are not supported.
+    pub arch: Option<Architecture>,
+
+    /// Separate debug file (e.g. from `objcopy --only-keep-debug`) for source resolution
+    pub debug_file: Option<String>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Elf,
+            map_source: false,
+            stack_section: None,
+            progress: false,
+            parallel: true,
+            normalize_names: true,
+            raw_names: false,
+            dedup: false,
+            raw_addresses: false,
+            arch: None,
+            debug_file: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Create default parse options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the ELF or object file mode
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Enable source location resolution
+    pub fn map_source(mut self, map_source: bool) -> Self {
+        self.map_source = map_source;
+        self
+    }
+
+    /// Set the expected architecture, files with a mismatched header will fail to parse
+    ///
+    /// Pointer width and endianness are not overridden, see [`ParseOptions::arch`].
+    pub fn arch(mut self, arch: Architecture) -> Self {
+        self.arch = Some(arch);
+        self
+    }
+
+    /// Enable periodic progress logging during source resolution
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Enable parallel function processing (enabled by default)
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Enable name normalisation (enabled by default), see [`normalize_name`]
+    pub fn normalize_names(mut self, normalize: bool) -> Self {
+        self.normalize_names = normalize;
+        self
+    }
+
+    /// Keep raw (mangled) symbol names, as shown by `nm` or linker maps
+    pub fn raw_names(mut self, raw: bool) -> Self {
+        self.raw_names = raw;
+        self
+    }
+
+    /// Merge aliased functions sharing an address into a single entry
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Keep raw symbol addresses, by default the Thumb bit is masked for ARM targets
+    pub fn raw_addresses(mut self, raw: bool) -> Self {
+        self.raw_addresses = raw;
+        self
+    }
+
+    /// Load DWARF information for source resolution from a separate debug file
+    pub fn debug_file(mut self, file: &str) -> Self {
+        self.debug_file = Some(file.to_string());
+        self
+    }
+
+    /// Override the stack size section name, for toolchains emitting
+    /// stack information under a non-standard section
+    pub fn stack_section(mut self, name: &str) -> Self {
+        self.stack_section = Some(name.to_string());
+        self
+    }
+}
+
+/// Aggregate totals over report functions
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    /// Number of functions
+    pub function_count: usize,
+
+    /// Total text size
+    pub total_text: u64,
+
+    /// Total stack size
+    pub total_stack: u64,
+
+    /// Largest single stack frame
+    pub max_stack: u64,
+}
+
+/// Stack size distribution over report functions
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Mean stack size
+    pub mean: f64,
+
+    /// Median (p50) stack size
+    pub median: u64,
+
+    /// 95th percentile stack size
+    pub p95: u64,
+
+    /// Largest stack size
+    pub max: u64,
+}
+
+/// Discrepancy between stack-sizes and DWARF derived function sizes
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeMismatch {
+    /// Function with mismatched size
+    pub function: Function,
+
+    /// DWARF derived size
+    pub dwarf: u64,
+}
+
+/// Policy for combining duplicate functions when merging reports
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum MergePolicy {
+    /// Sum values across duplicates
+    Sum,
+    /// Take the largest value
+    Max,
+    /// Keep the first value seen, skipping missing (zero) values
+    First,
+}
+
+impl MergePolicy {
+    fn apply(&self, a: u64, b: u64) -> u64 {
+        match self {
+            MergePolicy::Sum => a + b,
+            MergePolicy::Max => a.max(b),
+            MergePolicy::First if a == 0 => b,
+            MergePolicy::First => a,
+        }
+    }
 }
 
 /// Stack use report
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Report {
+    /// Report format version, see [`REPORT_VERSION`] (missing in v1 reports)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: u32,
+
     pub functions: Vec<Function>,
+
+    /// Referenced (demangled) functions without definitions or stack information
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub undefined: Vec<String>,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Function {
-    /// Memory address
+    /// Memory address, for object files this is a section-relative offset
     pub addr: u64,
 
     /// Full / demangled name
     pub name: String,
 
+    /// Original (mangled) symbol name
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mangled: String,
+
+    /// Alternative (demangled) names for the same code, e.g. aliases or weak symbols
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub aliases: Vec<String>,
+
     /// Text size
     pub text: u64,
 
@@ -51,27 +382,197 @@ pub struct Function {
     pub stack: u64,
 
     /// Function source location
-    #[serde(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
     pub source: String,
+
+    /// Inlined frames at the function address (`file:line (function)`), innermost first
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inline: Vec<String>,
+
+    /// Containing section name
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub section: String,
+
+    /// Names of called functions (requires relocation information)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub calls: Vec<String>,
+
+    /// Function ABI, inferred from symbol mangling
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub abi: Abi,
+}
+
+/// Function ABI, as inferred from the symbol name
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Abi {
+    /// Rust mangled symbol
+    #[default]
+    Rust,
+    /// Unmangled symbol (`#[no_mangle]` / `extern "C"`, or non-Rust code)
+    Extern,
+}
+
+impl Abi {
+    /// Infer ABI from a symbol name, unmangled names are `Extern`
+    pub fn from_symbol(name: &str) -> Self {
+        match format!("{:#}", demangle(name)) == name {
+            true => Abi::Extern,
+            false => Abi::Rust,
+        }
+    }
+}
+
+impl Function {
+    /// Stack to text size ratio, high values indicate large stack allocations
+    pub fn ratio(&self) -> f64 {
+        self.stack as f64 / self.text.max(1) as f64
+    }
+
+    /// Fetch the display name using the provided transform
+    pub fn display_name(&self, transform: &dyn NameTransform) -> String {
+        transform.transform(&self.name)
+    }
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            version: REPORT_VERSION,
+            functions: vec![],
+            undefined: vec![],
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Report {
+    type Item = &'a Function;
+    type IntoIter = std::slice::Iter<'a, Function>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.functions.iter()
+    }
 }
 
 impl Report {
     /// Parse a report from an ELF or object file
+    ///
+    /// See [`Report::parse_with`] for additional parsing options.
     pub fn parse(file: &str, mode: Mode, map_source: bool) -> Result<Report, anyhow::Error> {
+        let opts = ParseOptions::new().mode(mode).map_source(map_source);
+        Self::parse_with(file, &opts)
+    }
+
+    /// Parse a report from an ELF or object file with the provided options
+    pub fn parse_with(file: &str, opts: &ParseOptions) -> Result<Report, anyhow::Error> {
         // Load ELF file
         debug!("Loading object: {}", file);
         let b = std::fs::read(file)?;
 
+        Self::parse_bytes_with(&b, opts)
+    }
+
+    /// Parse a report from a GCC `-fstack-usage` (`.su`) file
+    ///
+    /// Functions have names, stack sizes and source locations only, and may be
+    /// combined with ELF reports via [`Report::merge`].
+    pub fn parse_su(file: &str) -> Result<Report, anyhow::Error> {
+        debug!("Loading stack usage: {}", file);
+        let s = std::fs::read_to_string(file)?;
+
+        Self::parse_su_str(&s)
+    }
+
+    /// Parse a report from `.su` stack usage data
+    pub fn parse_su_str(s: &str) -> Result<Report, anyhow::Error> {
+        Ok(Report {
+            functions: parse_su(s)?,
+            ..Default::default()
+        })
+    }
+
+    /// Parse a report from in-memory ELF or object file data
+    ///
+    /// See [`Report::parse_bytes_with`] for additional parsing options.
+    pub fn parse_bytes(data: &[u8], mode: Mode, map_source: bool) -> Result<Report, anyhow::Error> {
+        let opts = ParseOptions::new().mode(mode).map_source(map_source);
+        Self::parse_bytes_with(data, &opts)
+    }
+
+    /// Parse a report from in-memory ELF or object file data with the provided options
+    pub fn parse_bytes_with(b: &[u8], opts: &ParseOptions) -> Result<Report, anyhow::Error> {
+        // Stack usage files are text, without symbol information
+        if opts.mode == Mode::StackUsage {
+            return Self::parse_su_str(std::str::from_utf8(b)?);
+        }
+
+        // Determine architecture, checking against the hint if provided
+        let arch = detect_arch(b, opts.arch)?;
+        debug!("Architecture: {:?}", arch);
+
+        // Relocatable files (`.o`, kernel modules `.ko`) always use object mode
+        let mode = match (&opts.mode, is_relocatable(b)?) {
+            (Mode::Elf, true) => {
+                info!("Relocatable object detected, using object mode");
+                Mode::Object
+            }
+            (m, _) => m.clone(),
+        };
+
+        // Object file addresses are section-relative (all zero with `-ffunction-sections`)
+        // and DWARF is unrelocated, so lookups would resolve to shared or wrong locations
+        let map_source = opts.map_source && mode == Mode::Elf;
+        if opts.map_source && !map_source {
+            warn!("Source mapping is not supported for relocatable objects, map the linked executable instead");
+        }
+
+        // LLVM only emits stack size sections for ELF targets, other formats (Mach-O, PE)
+        // require stack sizes from a custom section via `stack_section`
+        let format = addr2line::object::File::parse(b)?.format();
+        if format != BinaryFormat::Elf && opts.stack_section.is_none() {
+            return Err(anyhow::anyhow!(
+                "{:?} binaries do not contain LLVM stack size information (only emitted for ELF \
+                 targets), specify a section with stack sizes via `--stack-section`",
+                format
+            ));
+        }
+
+        // Check for LLVM stack size information, a missing section usually means the
+        // build was not configured to emit it (missing custom sections only warn)
+        let section = opts.stack_section.as_deref().unwrap_or(STACK_SIZES_SECTION);
+        if section == STACK_SIZES_SECTION && !has_section(b, section)? {
+            return Err(anyhow::anyhow!(
+                "no `{}` section found, build with `RUSTFLAGS=\"-Z emit-stack-sizes\"` (nightly) \
+                 or `-C link-arg=--emit-stack-sizes`, and check the section is not stripped",
+                section
+            ));
+        }
+
         // Parse via stack sizes
         debug!("Parsing LLVM stack size information");
-        let parsed = match mode {
-            Mode::Elf => analyze_executable(&b[..])?,
-            Mode::Object => {
-                let _l = analyze_object(&b[..])?;
+        let mut parsed = match (format, &mode) {
+            (BinaryFormat::Elf, Mode::Elf) => load_executable(b, arch)?,
+            (BinaryFormat::Elf, Mode::Object) => load_object(b, arch)?,
+            _ => load_symbols(b, arch)?,
+        };
 
-                todo!("object mode not yet implemented");
+        // Mask the Thumb bit from ARM function addresses, restoring it from the
+        // symbol table if raw addresses are requested
+        if arch == Architecture::Arm {
+            let thumb = thumb_symbols(b)?;
+            for s in parsed.defined.iter_mut() {
+                s.addr &= !1;
+                if opts.raw_addresses && thumb.contains(&s.addr) {
+                    s.addr |= 1;
+                }
             }
-        };
+        }
+
+        if opts.stack_section.is_none() && parsed.defined.iter().all(|f| f.stack.is_none()) {
+            return Err(anyhow::anyhow!("no stack length information found"));
+        }
 
         info!(
             "Parsed {} functions ({} undefined)",
@@ -79,85 +580,1100 @@ impl Report {
             parsed.undefined.len()
         );
 
+        // Load stack sizes from a non-standard section if requested
+        let overrides = match opts
+            .stack_section
+            .as_deref()
+            .filter(|s| *s != STACK_SIZES_SECTION || format != BinaryFormat::Elf)
+        {
+            Some(name) => {
+                debug!("Loading stack sizes from section: {}", name);
+                load_stack_section(b, name)?
+            }
+            None => BTreeMap::new(),
+        };
+
+        // Load section table for section annotation, object files
+        // instead resolve sections via the symbol table
+        let sections = match mode {
+            Mode::Elf => load_sections(b)?,
+            _ => vec![],
+        };
+
+        // Load call relationships from relocations where available
+        let calls = load_calls(b, arch)?;
+
+        // Load separate debug file if provided, checking it matches the executable
+        let debug = match (&opts.debug_file, map_source) {
+            (Some(f), true) => {
+                debug!("Loading debug file: {}", f);
+                let d = std::fs::read(f)?;
+                check_build_id(b, &d[..])?;
+                Some(d)
+            }
+            _ => None,
+        };
+
         // Load Dwarf context for source->line resolution
-        // TODO: this is broken atm
+        let dwarf = debug.as_deref().unwrap_or(b);
         let ctx = match map_source {
-            true => Some(DwarfContext::load(&b[..])?),
+            true => match DwarfContext::load(dwarf) {
+                Ok(c) => Some(c),
+                Err(e) => {
+                    warn!("Failed to load DWARF, source mapping disabled: {}", e);
+                    None
+                }
+            },
             false => None,
         };
 
-        // Process functions into report format
-        let mut functions = vec![];
-        for (addr, f) in parsed.defined.iter() {
-            // Demangle name
-            let name = format!("{:#}", demangle(f.names()[0]));
-
-            // Fetch text and stack sizes
-            let text = f.size();
-            let stack = f.stack().unwrap_or(0);
-
-            // Attempt to resolve source line
-            let source = match ctx.as_ref().map(|d| d.get_line(f.names()[0], *addr)) {
-                Some(Ok(Some(v))) => v,
-                _ => "".to_string(),
-            };
-
-            functions.push(Function {
-                name,
-                addr: *addr,
-                stack,
-                text,
-                source,
-            })
+        // Resolve source locations up front, in parallel where enabled
+        let (locations, dwarf_errors) = match &ctx {
+            Some(ctx) => resolve_locations(&parsed.defined, ctx, dwarf, opts),
+            None => (vec![], 0),
+        };
+
+        // Process functions into report format, in parallel where enabled
+        let process = |(i, f): (usize, &Symbol)| {
+            process_symbol(
+                f,
+                arch,
+                locations.get(i),
+                &overrides,
+                &sections,
+                &calls,
+                opts,
+            )
+        };
+        let mut functions: Vec<_> = match opts.parallel {
+            true => parsed.defined.par_iter().enumerate().map(process).collect(),
+            false => parsed.defined.iter().enumerate().map(process).collect(),
+        };
+
+        if dwarf_errors > 0 {
+            warn!(
+                "Failed to resolve source for {} functions due to DWARF errors",
+                dwarf_errors
+            );
+        }
+
+        if calls.is_empty() {
+            debug!("No relocation information found, call graph unavailable");
         }
 
         // Sort functions by address
         functions.sort_by_key(|f| f.addr);
 
+        // Collect undefined functions
+        let mut undefined: Vec<_> = parsed
+            .undefined
+            .iter()
+            .map(|n| demangle_name(n, opts))
+            .collect();
+        undefined.sort();
+        undefined.dedup();
+
+        let mut report = Report {
+            functions,
+            undefined,
+            ..Default::default()
+        };
+
+        // Merge aliased functions if enabled
+        if opts.dedup {
+            report.dedup();
+        }
+
         // Return report
-        Ok(Report { functions })
+        Ok(report)
     }
 
-    /// Apply a sort to the internal report
-    pub fn sort(&mut self, sort: Sort) {
-        match sort {
-            Sort::Text => {
-                self.functions.sort_by_key(|f| f.text);
-                self.functions.reverse();
+    /// Merge functions sharing an address (and section) into a single entry
+    ///
+    /// The first function is kept, with the names of the others recorded as
+    /// aliases so that totals are not inflated by aliased or weak symbols.
+    /// Functions without text (and thus without a meaningful address) are
+    /// left as-is.
+    pub fn dedup(&mut self) {
+        let mut index: HashMap<(String, u64), usize> = HashMap::new();
+        let mut functions: Vec<Function> = Vec::with_capacity(self.functions.len());
+
+        for f in self.functions.drain(..) {
+            if f.text == 0 {
+                functions.push(f);
+                continue;
             }
-            Sort::Stack => {
-                self.functions.sort_by_key(|f| f.stack);
-                self.functions.reverse();
+
+            match index.get(&(f.section.clone(), f.addr)) {
+                Some(i) => {
+                    let e = &mut functions[*i];
+                    debug!("Merging {} into alias {}", f.name, e.name);
+
+                    e.stack = e.stack.max(f.stack);
+                    e.text = e.text.max(f.text);
+                    e.aliases.push(f.name);
+                    e.aliases.extend(f.aliases);
+                    e.calls.extend(f.calls);
+                    e.calls.sort();
+                    e.calls.dedup();
+                }
+                None => {
+                    index.insert((f.section.clone(), f.addr), functions.len());
+                    functions.push(f);
+                }
             }
-            Sort::Address => {
-                self.functions.sort_by_key(|f| f.addr);
+        }
+
+        for f in functions.iter_mut() {
+            let name = f.name.clone();
+            f.aliases.retain(|a| a != &name);
+            f.aliases.sort();
+            f.aliases.dedup();
+        }
+
+        self.functions = functions;
+    }
+
+    /// Apply a sort to the internal report
+    ///
+    /// Ties are broken by address then name, for deterministic ordering.
+    pub fn sort(&mut self, sort: Sort, order: Order) {
+        self.sort_keys(&[(sort, order)])
+    }
+
+    /// Apply sort keys in priority order, ties are broken by address then name
+    pub fn sort_keys(&mut self, keys: &[(Sort, Order)]) {
+        self.sort_with(keys, |s, a, b| s.compare(a, b))
+    }
+
+    /// Apply sort keys as with [`Report::sort_keys`], using worst case cumulative
+    /// stack (e.g. from [`Report::max_depth`]) in place of stack
+    pub fn sort_cumulative(&mut self, keys: &[(Sort, Order)], depths: &HashMap<String, u64>) {
+        let depth = |f: &Function| depths.get(&f.name).copied().unwrap_or(f.stack);
+        self.sort_with(keys, |s, a, b| match s {
+            Sort::Stack => depth(a).cmp(&depth(b)),
+            _ => s.compare(a, b),
+        })
+    }
+
+    fn sort_with<C>(&mut self, keys: &[(Sort, Order)], compare: C)
+    where
+        C: Fn(&Sort, &Function, &Function) -> std::cmp::Ordering,
+    {
+        self.functions.sort_by(|a, b| {
+            keys.iter()
+                .map(|(sort, order)| order.apply(compare(sort, a, b)))
+                .fold(std::cmp::Ordering::Equal, |acc, o| acc.then(o))
+                .then(a.addr.cmp(&b.addr))
+                .then(a.name.cmp(&b.name))
+        });
+    }
+
+    /// Parse file data, reusing a cached report from `cache_dir` if the data and options are unchanged
+    ///
+    /// Cache entries are keyed by a hash of the crate version, file data, debug file
+    /// contents (if set) and parse options.
+    #[cfg(feature = "serde")]
+    pub fn parse_cached(
+        data: &[u8],
+        opts: &ParseOptions,
+        cache_dir: &str,
+    ) -> Result<Report, anyhow::Error> {
+        // Progress output and parallelism do not affect the report
+        let key_opts = ParseOptions {
+            progress: false,
+            parallel: true,
+            ..opts.clone()
+        };
+        let mut key = fnv1a(FNV_OFFSET, env!("CARGO_PKG_VERSION").as_bytes());
+        key = fnv1a(key, data);
+        if let Some(f) = &opts.debug_file {
+            key = fnv1a(key, &std::fs::read(f)?);
+        }
+        key = fnv1a(key, format!("{:?}", key_opts).as_bytes());
+
+        let path = std::path::Path::new(cache_dir).join(format!("{:016x}.json", key));
+        if path.exists() {
+            match std::fs::read(&path).map(|c| serde_json::from_slice::<Report>(&c)) {
+                Ok(Ok(r)) if r.version == REPORT_VERSION => {
+                    debug!("Loaded cached report: {}", path.display());
+                    return Ok(r);
+                }
+                _ => warn!("Ignoring invalid cache entry: {}", path.display()),
             }
         }
+
+        let r = Report::parse_bytes_with(data, opts)?;
+
+        std::fs::create_dir_all(cache_dir)?;
+        std::fs::write(&path, serde_json::to_vec(&r)?)?;
+        debug!("Cached report: {}", path.display());
+
+        Ok(r)
+    }
+
+    /// Retain only functions matching the predicate, mirroring `Vec::retain`
+    pub fn retain<F: FnMut(&Function) -> bool>(&mut self, f: F) {
+        self.functions.retain(f);
+    }
+
+    /// Sort functions by a custom expression, ties are broken by address then name
+    pub fn sort_by_expr(&mut self, expr: &SortExpr, order: Order) {
+        self.functions.sort_by(|a, b| {
+            order
+                .apply(expr.eval(a).total_cmp(&expr.eval(b)))
+                .then(a.addr.cmp(&b.addr))
+                .then(a.name.cmp(&b.name))
+        });
     }
 
     /// Load a report from file
+    ///
+    /// TOML and YAML formats are detected by extension, JSON or CSV by extension or content.
+    #[cfg(feature = "serde")]
     pub fn load(file: &str) -> Result<Report, anyhow::Error> {
-        // Open file
-        let f = std::fs::File::open(file)?;
+        // Read file
+        let d = std::fs::read(file)?;
+
+        // Parse report
+        let mut r: Report = match OutputFormat::from_path(file) {
+            OutputFormat::Toml => toml::from_str(std::str::from_utf8(&d)?)?,
+            OutputFormat::Yaml => serde_yaml::from_slice(&d)?,
+            _ if is_csv(file, &d) => load_csv(&d)?,
+            _ => serde_json::from_slice(&d)?,
+        };
 
-        // Read report
-        let r = serde_json::from_reader(f)?;
+        // Check version, unversioned reports predate versioning (v1)
+        if r.version == 0 {
+            r.version = 1;
+        }
+        if r.version > REPORT_VERSION {
+            return Err(anyhow::anyhow!(
+                "report {} has version {}, newer than supported version {}",
+                file,
+                r.version,
+                REPORT_VERSION
+            ));
+        }
+        if r.version < REPORT_VERSION {
+            warn!(
+                "Report {} has older version {} (current {})",
+                file, r.version, REPORT_VERSION
+            );
+        }
 
         Ok(r)
     }
 
-    /// Write a report to file
-    pub fn save(&self, file: &str) -> Result<(), anyhow::Error> {
-        // Encode to JSON
-        let s = serde_json::to_string_pretty(self)?;
+    /// Write a report to file, in a format detected by extension (defaulting to JSON)
+    ///
+    /// `canonical` sorts functions by name (then address) prior to encoding,
+    /// for deterministic output independent of the current sort.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, file: &str, canonical: bool) -> Result<(), anyhow::Error> {
+        self.save_as(file, OutputFormat::from_path(file), canonical)
+    }
+
+    /// Encode report to CSV, with diff columns where a previous report is provided
+    ///
+    /// With a previous report rows follow [`Report::diff`], including removed functions.
+    #[cfg(feature = "serde")]
+    pub fn to_csv(&self, prev: Option<&Report>) -> Result<String, anyhow::Error> {
+        match prev {
+            Some(p) => {
+                let changes: Vec<_> = self.diff(p).iter().map(FunctionChange::from).collect();
+                changes_to_csv(&changes)
+            }
+            None => Ok(String::from_utf8(save_csv(self)?)?),
+        }
+    }
+
+    /// Save report to a file in the specified format
+    ///
+    /// See [`Report::save`] for canonical ordering.
+    #[cfg(feature = "serde")]
+    pub fn save_as(
+        &self,
+        file: &str,
+        format: OutputFormat,
+        canonical: bool,
+    ) -> Result<(), anyhow::Error> {
+        // Stamp the current version, and apply canonical ordering if enabled
+        let mut r = self.clone();
+        r.version = REPORT_VERSION;
+        if canonical {
+            r.functions
+                .sort_by(|a, b| a.name.cmp(&b.name).then(a.addr.cmp(&b.addr)));
+        }
+
+        // Encode in the selected format
+        let d = match format {
+            OutputFormat::Json => serde_json::to_string_pretty(&r)?.into_bytes(),
+            OutputFormat::Csv => save_csv(&r)?,
+            OutputFormat::Toml => toml::to_string_pretty(&r)?.into_bytes(),
+            OutputFormat::Yaml => serde_yaml::to_string(&r)?.into_bytes(),
+        };
+
         // Write to file
-        std::fs::write(file, s.as_bytes())?;
+        std::fs::write(file, d)?;
 
         Ok(())
     }
 
+    /// Merge reports (e.g. from multiple translation units), combining same-named functions
+    ///
+    /// `Max` is the safe default for stack as inline functions emitted in multiple
+    /// units may be compiled differently, and the worst case is what must fit.
+    pub fn merge(reports: Vec<Report>, text: MergePolicy, stack: MergePolicy) -> Report {
+        let mut functions: Vec<Function> = vec![];
+        let mut index = HashMap::new();
+        let mut undefined = BTreeSet::new();
+
+        for r in reports {
+            undefined.extend(r.undefined);
+            for f in r.functions {
+                match index.get(&f.name) {
+                    Some(i) => {
+                        let e: &mut Function = &mut functions[*i];
+                        e.text = text.apply(e.text, f.text);
+                        e.stack = stack.apply(e.stack, f.stack);
+
+                        // Fill in details missing from the first source, e.g. `.su` entries
+                        // have no address or symbol information
+                        if e.addr == 0 {
+                            e.addr = f.addr;
+                        }
+                        if e.mangled.is_empty() {
+                            e.mangled = f.mangled;
+                        }
+                        if e.section.is_empty() {
+                            e.section = f.section;
+                        }
+                        if e.source.is_empty() {
+                            e.source = f.source;
+                        }
+                        for c in f.calls {
+                            if !e.calls.contains(&c) {
+                                e.calls.push(c);
+                            }
+                        }
+                    }
+                    None => {
+                        index.insert(f.name.clone(), functions.len());
+                        functions.push(f);
+                    }
+                }
+            }
+        }
+
+        Report {
+            undefined: resolve_undefined(undefined, &functions),
+            functions,
+            ..Default::default()
+        }
+    }
+
+    /// Concatenate reports (e.g. from unlinked object files), keeping duplicate functions distinct
+    ///
+    /// Object file addresses are section-relative so same-named functions from
+    /// different inputs are not combined, see [`Report::duplicates`] to find these.
+    pub fn concat(reports: Vec<Report>) -> Report {
+        let mut functions = vec![];
+        let mut undefined = BTreeSet::new();
+        for r in reports {
+            functions.extend(r.functions);
+            undefined.extend(r.undefined);
+        }
+
+        let r = Report {
+            undefined: resolve_undefined(undefined, &functions),
+            functions,
+            ..Default::default()
+        };
+
+        for (name, count) in r.duplicates() {
+            warn!("Function {} found {} times", name, count);
+        }
+
+        r
+    }
+
+    /// Find function names occurring more than once, with occurrence counts
+    pub fn duplicates(&self) -> BTreeMap<&str, usize> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for f in &self.functions {
+            *counts.entry(f.name.as_str()).or_default() += 1;
+        }
+        counts.retain(|_, c| *c > 1);
+        counts
+    }
+
+    /// Generate a JSON schema for the report format
+    #[cfg(feature = "schema")]
+    pub fn schema() -> Result<String, anyhow::Error> {
+        let schema = schemars::schema_for!(Report);
+        Ok(serde_json::to_string_pretty(&schema)?)
+    }
+
+    /// Iterate over report functions
+    ///
+    /// ```
+    /// # use whatthestack::{Function, Report};
+    /// let report = Report {
+    ///     functions: vec![Function { stack: 64, ..Default::default() }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(report.iter().filter(|f| f.stack > 32).count(), 1);
+    /// assert_eq!(report.iter().map(|f| f.stack).sum::<u64>(), 64);
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<'_, Function> {
+        self.functions.iter()
+    }
+
+    /// Iterate over functions matching a predicate
+    ///
+    /// ```
+    /// # use whatthestack::{Function, Report};
+    /// let report = Report {
+    ///     functions: vec![
+    ///         Function { name: "a::small".to_string(), stack: 8, ..Default::default() },
+    ///         Function { name: "a::large".to_string(), stack: 256, ..Default::default() },
+    ///     ],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let large: Vec<_> = report.filter_by(|f| f.stack > 128).map(|f| &f.name).collect();
+    /// assert_eq!(large, ["a::large"]);
+    /// ```
+    pub fn filter_by<'a, P>(&'a self, pred: P) -> impl Iterator<Item = &'a Function> + 'a
+    where
+        P: FnMut(&&'a Function) -> bool + 'a,
+    {
+        self.functions.iter().filter(pred)
+    }
+
     /// Find a function by name
     pub fn find(&self, name: &str) -> Option<&Function> {
         self.functions.iter().find(|f| f.name == name)
     }
+
+    /// Find all functions with full or compressed (see [`compress_name`]) names containing a substring
+    pub fn find_all(&self, needle: &str) -> Vec<&Function> {
+        self.functions
+            .iter()
+            .filter(|f| f.name.contains(needle) || compress_name(&f.name).contains(needle))
+            .collect()
+    }
+
+    /// Find functions fuzzy matching a query, ranked by score (best first)
+    pub fn find_fuzzy(&self, query: &str) -> Vec<(&Function, i64)> {
+        let mut matches: Vec<_> = self
+            .functions
+            .iter()
+            .filter_map(|f| fuzzy_score(query, &f.name).map(|s| (f, s)))
+            .collect();
+
+        // Prefer shorter names for equal scores
+        matches.sort_by(|(a, sa), (b, sb)| sb.cmp(sa).then(a.name.len().cmp(&b.name.len())));
+
+        matches
+    }
+
+    /// Rename functions (and calls) with the provided prefix, for matching across crate renames
+    pub fn rename_prefix(&mut self, from: &str, to: &str) {
+        let rename = |n: &mut String| {
+            if let Some(rest) = n.strip_prefix(from) {
+                *n = format!("{}{}", to, rest);
+            }
+        };
+
+        for f in self.functions.iter_mut() {
+            rename(&mut f.name);
+            f.calls.iter_mut().for_each(rename);
+        }
+    }
+
+    /// Find functions defined in a source file (requires source mapping)
+    ///
+    /// Paths match exactly or by suffix, such that `src/foo.rs` matches `/path/to/src/foo.rs`.
+    pub fn functions_in_file(&self, file: &str) -> Vec<&Function> {
+        let suffix = format!("/{}", file.trim_start_matches('/'));
+
+        self.functions
+            .iter()
+            .filter(|f| match source_file(&f.source) {
+                Some(p) => p == file || p.ends_with(&suffix),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Sum stack use by source file (requires source mapping)
+    pub fn stack_by_file(&self) -> BTreeMap<String, u64> {
+        let mut files = BTreeMap::new();
+        for f in &self.functions {
+            if let Some(p) = source_file(&f.source) {
+                *files.entry(p.to_string()).or_default() += f.stack;
+            }
+        }
+        files
+    }
+
+    /// Compare text sizes against DWARF derived function sizes from the provided file data,
+    /// returning functions differing by more than `tolerance` bytes
+    pub fn verify_sizes(
+        &self,
+        d: &[u8],
+        tolerance: u64,
+    ) -> Result<Vec<SizeMismatch>, anyhow::Error> {
+        let sizes = load_function_sizes(d)?;
+
+        let mismatches = self
+            .functions
+            .iter()
+            .filter_map(|f| {
+                let dwarf = sizes.get(&f.addr).or_else(|| sizes.get(&(f.addr & !1)))?;
+                match f.text.abs_diff(*dwarf) > tolerance {
+                    true => Some(SizeMismatch {
+                        function: f.clone(),
+                        dwarf: *dwarf,
+                    }),
+                    false => None,
+                }
+            })
+            .collect();
+
+        Ok(mismatches)
+    }
+
+    /// Summarise functions by crate, returning (function count, total text, total stack)
+    ///
+    /// Functions without a recognisable crate prefix are grouped under `<unknown>`.
+    pub fn crates(&self) -> BTreeMap<String, (usize, u64, u64)> {
+        let mut crates: BTreeMap<String, (usize, u64, u64)> = BTreeMap::new();
+        for f in &self.functions {
+            let c = crate_name(&f.name).unwrap_or(UNKNOWN_CRATE);
+            let e = crates.entry(c.to_string()).or_default();
+            e.0 += 1;
+            e.1 += f.text;
+            e.2 += f.stack;
+        }
+        crates
+    }
+
+    /// Sum text and stack per crate, ordered by descending stack
+    ///
+    /// Functions without a recognisable crate prefix are grouped under `<unknown>`.
+    pub fn group_by_crate(&self) -> Vec<(String, u64, u64)> {
+        self.crates_sorted(&Sort::Stack, Order::Desc)
+            .into_iter()
+            .map(|(name, (_, text, stack))| (name, text, stack))
+            .collect()
+    }
+
+    /// Summarise function count, text and stack per crate, ordered by the provided sort
+    ///
+    /// Crates are sorted by summed text or stack, stack to text ratio, or name
+    /// (for `Name` and `Address` sorts), ties remain ordered by name.
+    pub fn crates_sorted(&self, sort: &Sort, order: Order) -> Vec<(String, (usize, u64, u64))> {
+        let mut crates: Vec<_> = self.crates().into_iter().collect();
+
+        let ratio = |(_, text, stack): &(usize, u64, u64)| *stack as f64 / (*text).max(1) as f64;
+        crates.sort_by(|(na, a), (nb, b)| {
+            order.apply(match sort {
+                Sort::Text => a.1.cmp(&b.1),
+                Sort::Stack => a.2.cmp(&b.2),
+                Sort::Ratio => ratio(a).total_cmp(&ratio(b)),
+                Sort::Name | Sort::Address => na.cmp(nb),
+            })
+        });
+
+        crates
+    }
+
+    /// Render functions as folded stacks (`crate;module;function stack`) for flamegraph tools
+    ///
+    /// Paths are split with [`module_path`], consistent with [`Report::group_by_crate`].
+    pub fn folded(&self) -> String {
+        self.functions
+            .iter()
+            .map(|f| format!("{} {}\n", module_path(&f.name).join(";"), f.stack))
+            .collect()
+    }
+
+    /// Map function addresses to names, sorted for binary search by external symbolizers
+    ///
+    /// Aliased addresses keep the last function name, object file addresses are
+    /// section-relative and so may collide.
+    pub fn address_map(&self) -> BTreeMap<u64, String> {
+        self.functions
+            .iter()
+            .map(|f| (f.addr, f.name.clone()))
+            .collect()
+    }
+
+    /// Compute a deterministic fingerprint of the report
+    ///
+    /// This covers sorted (name, text, stack) tuples, ignoring addresses and
+    /// source locations which vary between otherwise identical builds.
+    pub fn fingerprint(&self) -> u64 {
+        let mut entries: Vec<_> = self
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.text, f.stack))
+            .collect();
+        entries.sort();
+
+        // FNV-1a, stable across platforms and toolchains
+        let mut h = FNV_OFFSET;
+        for (name, text, stack) in entries {
+            h = fnv1a(h, name.as_bytes());
+            h = fnv1a(h, &[0]);
+            h = fnv1a(h, &text.to_le_bytes());
+            h = fnv1a(h, &stack.to_le_bytes());
+        }
+
+        h
+    }
+
+    /// Compute aggregate totals over report functions
+    pub fn summary(&self) -> Summary {
+        Summary {
+            function_count: self.functions.len(),
+            total_text: self.functions.iter().map(|f| f.text).sum(),
+            total_stack: self.functions.iter().map(|f| f.stack).sum(),
+            max_stack: self.functions.iter().map(|f| f.stack).max().unwrap_or(0),
+        }
+    }
+
+    /// Compute the stack size distribution over report functions
+    pub fn stats(&self) -> Stats {
+        let mut stacks: Vec<_> = self.functions.iter().map(|f| f.stack).collect();
+        stacks.sort();
+
+        if stacks.is_empty() {
+            return Stats::default();
+        }
+
+        Stats {
+            mean: stacks.iter().sum::<u64>() as f64 / stacks.len() as f64,
+            median: percentile(&stacks, 50.0),
+            p95: percentile(&stacks, 95.0),
+            max: stacks[stacks.len() - 1],
+        }
+    }
+
+    /// Compute the worst case cumulative stack (including callees) for each function
+    ///
+    /// This requires call information, without which each value is the function's
+    /// own stack. Recursion is detected and cut, logging a warning.
+    pub fn max_depth(&self) -> Vec<(Function, u64)> {
+        let (depths, recursive) = CallGraph::new(self).max_depths();
+        if recursive {
+            warn!("Recursion detected, worst case stack may be unbounded");
+        }
+
+        self.functions
+            .iter()
+            .map(|f| {
+                (
+                    f.clone(),
+                    depths.get(f.name.as_str()).copied().unwrap_or(f.stack),
+                )
+            })
+            .collect()
+    }
+
+    /// Compute the worst case stack call chain from the named root function
+    ///
+    /// Returns the frames along the deepest path and whether recursion was detected.
+    pub fn worst_path(&self, root: &str) -> Option<(Vec<Frame>, bool)> {
+        CallGraph::new(self).worst_path(root)
+    }
+
+    /// Remove functions not transitively called from the named root function
+    ///
+    /// Returns the number of functions pruned.
+    pub fn prune_unreachable(&mut self, root: &str) -> Result<usize, anyhow::Error> {
+        let reachable: HashSet<String> = CallGraph::new(self)
+            .reachable(root)
+            .ok_or_else(|| anyhow::anyhow!("root function '{}' not found", root))?
+            .into_iter()
+            .map(|n| n.to_string())
+            .collect();
+
+        let n = self.functions.len();
+        self.retain(|f| reachable.contains(&f.name));
+
+        Ok(n - self.functions.len())
+    }
+
+    /// Index functions by name, keeping the first of any duplicate names (as [`Report::find`])
+    pub fn by_name(&self) -> HashMap<&str, &Function> {
+        let mut index = HashMap::with_capacity(self.functions.len());
+        for f in &self.functions {
+            index.entry(f.name.as_str()).or_insert(f);
+        }
+        index
+    }
+
+    /// Compute per-function changes against a previous report, matching by name
+    pub fn diff(&self, prev: &Report) -> Vec<FunctionDiff> {
+        let (current, previous) = (self.by_name(), prev.by_name());
+
+        // Match current functions against previous
+        let mut diffs: Vec<_> = self
+            .functions
+            .iter()
+            .map(|f| FunctionDiff {
+                name: f.name.clone(),
+                prev: previous.get(f.name.as_str()).map(|&p| p.clone()),
+                current: Some(f.clone()),
+            })
+            .collect();
+
+        // Add functions removed since previous report
+        for f in prev
+            .functions
+            .iter()
+            .filter(|f| !current.contains_key(f.name.as_str()))
+        {
+            diffs.push(FunctionDiff {
+                name: f.name.clone(),
+                prev: Some(f.clone()),
+                current: None,
+            });
+        }
+
+        diffs
+    }
+
+    /// Compare against a previous report, matching by name, see [`Diff`]
+    pub fn compare(&self, prev: &Report) -> Diff {
+        Diff::new(&self.diff(prev))
+    }
+}
+
+/// Current report format version
+///
+/// Increment on incompatible changes to the serialised report layout.
+pub const REPORT_VERSION: u32 = 1;
+
+/// Crate name for functions without a recognisable crate prefix
+pub const UNKNOWN_CRATE: &str = "<unknown>";
+
+/// Interval for progress logging
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default LLVM stack size section name
+pub const STACK_SIZES_SECTION: &str = ".stack_sizes";
+
+/// Drop undefined functions that are defined in another merged report
+fn resolve_undefined(undefined: BTreeSet<String>, functions: &[Function]) -> Vec<String> {
+    let defined: HashSet<_> = functions.iter().map(|f| f.name.as_str()).collect();
+    undefined
+        .into_iter()
+        .filter(|n| !defined.contains(n.as_str()))
+        .collect()
+}
+
+/// Demangle a symbol name, normalising if enabled
+fn demangle_name(name: &str, opts: &ParseOptions) -> String {
+    if opts.raw_names {
+        return name.to_string();
+    }
+
+    let n = format!("{:#}", demangle(name));
+    match opts.normalize_names {
+        true => normalize_name(&n),
+        false => n,
+    }
+}
+
+/// Resolved source location and inlined frames for a function
+#[derive(Clone, Debug, Default)]
+struct Location {
+    source: String,
+    inline: Vec<String>,
+}
+
+/// Resolve source locations for loaded symbols, returning the number of failed DWARF lookups
+///
+/// `ObjectContext` is not `Send`, so for parallel resolution symbols are split into a chunk per
+/// worker, the first resolved with `ctx` on this thread and the others with their own context
+/// loaded from `dwarf`.
+fn resolve_locations(
+    symbols: &[Symbol],
+    ctx: &DwarfContext,
+    dwarf: &[u8],
+    opts: &ParseOptions,
+) -> (Vec<Location>, usize) {
+    let workers = match opts.parallel {
+        true => rayon::current_num_threads(),
+        false => 1,
+    };
+    let done = AtomicUsize::new(0);
+
+    let resolve = |chunk: &[Symbol], ctx: &DwarfContext, progress: bool| {
+        let mut errors = 0;
+        let mut last_progress = Instant::now();
+        let locations: Vec<_> = chunk
+            .iter()
+            .map(|f| {
+                // Report progress for (slow) source resolution
+                if progress && last_progress.elapsed() > PROGRESS_INTERVAL {
+                    let n = done.load(Ordering::Relaxed);
+                    info!("Resolved {}/{} functions", n, symbols.len());
+                    last_progress = Instant::now();
+                }
+
+                let (location, error) = resolve_location(f, ctx);
+                if error {
+                    errors += 1;
+                }
+                done.fetch_add(1, Ordering::Relaxed);
+                location
+            })
+            .collect();
+        (locations, errors)
+    };
+    let resolve = &resolve;
+
+    let mut chunks = symbols.chunks(symbols.len().div_ceil(workers).max(1));
+    let first = chunks.next().unwrap_or_default();
+
+    std::thread::scope(|s| {
+        let handles: Vec<_> = chunks
+            .map(|c| {
+                s.spawn(move || match DwarfContext::load_quiet(dwarf) {
+                    Ok(ctx) => resolve(c, &ctx, false),
+                    Err(e) => {
+                        debug!("Failed to load DWARF for worker: {}", e);
+                        (vec![Location::default(); c.len()], c.len())
+                    }
+                })
+            })
+            .collect();
+
+        let (mut locations, mut errors) = resolve(first, ctx, opts.progress);
+        for h in handles {
+            let (l, e) = h.join().expect("source resolution worker panicked");
+            locations.extend(l);
+            errors += e;
+        }
+        (locations, errors)
+    })
+}
+
+/// Resolve the source location for a loaded symbol, returning whether DWARF lookup failed
+fn resolve_location(f: &Symbol, ctx: &DwarfContext) -> (Location, bool) {
+    let mut error = false;
+
+    // Attempt to resolve source line
+    let source = match ctx.get_line(f.names[0], f.addr, f.size) {
+        Ok(Some(v)) => v,
+        Ok(None) => "".to_string(),
+        Err(e) => {
+            debug!("DWARF lookup failed for {}: {}", f.names[0], e);
+            error = true;
+            "".to_string()
+        }
+    };
+
+    // Resolve inlined frames, where more than the function itself is found
+    let inline = match ctx.inline_frames(f.addr) {
+        Ok(frames) if frames.len() > 1 => frames,
+        Ok(_) => vec![],
+        Err(e) => {
+            debug!("DWARF frame lookup failed for {}: {}", f.names[0], e);
+            vec![]
+        }
+    };
+
+    (Location { source, inline }, error)
+}
+
+/// Convert a loaded symbol to a report function, with a previously resolved source location
+fn process_symbol(
+    f: &Symbol,
+    arch: Architecture,
+    location: Option<&Location>,
+    overrides: &BTreeMap<u64, u64>,
+    sections: &[(u64, u64, String)],
+    calls: &HashMap<String, BTreeSet<String>>,
+    opts: &ParseOptions,
+) -> Function {
+    let addr = &f.addr;
+
+    // Demangle name
+    let name = demangle_name(f.names[0], opts);
+
+    // Fetch text and stack sizes, section entries for Thumb functions may have the low address bit set
+    let text = f.size;
+    let stack = match arch {
+        Architecture::Arm => overrides
+            .get(&(*addr | 1))
+            .or_else(|| overrides.get(&(*addr & !1))),
+        _ => overrides.get(addr),
+    };
+    let stack = stack.copied().or(f.stack).unwrap_or(0);
+
+    let Location { source, inline } = location.cloned().unwrap_or_default();
+
+    // Lookup containing section
+    let section = match &f.section {
+        Some(s) => s.clone(),
+        None => sections
+            .iter()
+            .find(|(start, end, _)| *addr >= *start && *addr < *end)
+            .map(|(_, _, n)| n.clone())
+            .unwrap_or_default(),
+    };
+
+    // Resolve called functions across all symbol aliases
+    let mut callees: Vec<_> = f
+        .names
+        .iter()
+        .filter_map(|n| calls.get(*n))
+        .flatten()
+        .map(|c| demangle_name(c, opts))
+        .collect();
+    callees.sort();
+    callees.dedup();
+
+    // Demangle alternative names
+    let mut aliases: Vec<_> = f.names[1..]
+        .iter()
+        .map(|n| demangle_name(n, opts))
+        .filter(|n| n != &name)
+        .collect();
+    aliases.sort();
+    aliases.dedup();
+
+    Function {
+        name,
+        mangled: f.names[0].to_string(),
+        aliases,
+        addr: *addr,
+        stack,
+        text,
+        source,
+        inline,
+        section,
+        calls: callees,
+        abi: Abi::from_symbol(f.names[0]),
+    }
+}
+
+/// Check whether a file is a relocatable object
+fn is_relocatable(d: &[u8]) -> Result<bool, anyhow::Error> {
+    let object = addr2line::object::File::parse(d)?;
+    Ok(object.kind() == ObjectKind::Relocatable)
+}
+
+/// Check whether a file contains a section with the provided name
+fn has_section(d: &[u8], name: &str) -> Result<bool, anyhow::Error> {
+    let object = addr2line::object::File::parse(d)?;
+    Ok(object.sections().any(|s| s.name() == Ok(name)))
+}
+
+/// Detect the file architecture, validating against an optional hint
+///
+/// The hint is only used where the header architecture is unknown.
+fn detect_arch(d: &[u8], hint: Option<Architecture>) -> Result<Architecture, anyhow::Error> {
+    let detected = addr2line::object::File::parse(d)?.architecture();
+
+    match (hint, detected) {
+        (None, a) => Ok(a),
+        (Some(h), Architecture::Unknown) => Ok(h),
+        (Some(h), a) if h == a => Ok(a),
+        (Some(h), a) => Err(anyhow::anyhow!(
+            "architecture mismatch, expected {:?} but file header specifies {:?}",
+            h,
+            a
+        )),
+    }
+}
+
+/// Load address -> stack size entries from a `.stack_sizes` format section
+fn load_stack_section(d: &[u8], name: &str) -> Result<BTreeMap<u64, u64>, anyhow::Error> {
+    let object = addr2line::object::File::parse(d)?;
+
+    let mut sizes = BTreeMap::new();
+    let data = match object.section_by_name(name) {
+        Some(s) => s.data()?,
+        None => {
+            warn!("Stack size section '{}' not found", name);
+            return Ok(sizes);
+        }
+    };
+
+    // Entries are an address followed by a ULEB128 stack size, COFF files always
+    // report as 32-bit so prefer the architecture address size
+    let width = match object.architecture().address_size() {
+        Some(s) => s.bytes() as usize,
+        None if object.is_64() => 8,
+        None => 4,
+    };
+    let mut i = 0;
+    while i + width <= data.len() {
+        let mut b = [0u8; 8];
+        let addr = if object.is_little_endian() {
+            b[..width].copy_from_slice(&data[i..i + width]);
+            u64::from_le_bytes(b)
+        } else {
+            b[8 - width..].copy_from_slice(&data[i..i + width]);
+            u64::from_be_bytes(b)
+        };
+        i += width;
+
+        let mut stack = 0u64;
+        let mut shift = 0;
+        let mut complete = false;
+        while let Some(v) = data.get(i) {
+            i += 1;
+            stack |= ((v & 0x7f) as u64) << shift;
+            shift += 7;
+            if v & 0x80 == 0 || shift >= 64 {
+                complete = true;
+                break;
+            }
+        }
+
+        // Skip entries truncated by the end of the section
+        if !complete {
+            warn!(
+                "Truncated stack size entry for 0x{:08x} in '{}'",
+                addr, name
+            );
+            break;
+        }
+
+        sizes.insert(addr, stack);
+    }
+
+    Ok(sizes)
+}
+
+/// Load (start, end, name) address ranges for code and data sections
+fn load_sections(d: &[u8]) -> Result<Vec<(u64, u64, String)>, anyhow::Error> {
+    let object = addr2line::object::File::parse(d)?;
+
+    let sections = object
+        .sections()
+        .filter(|s| {
+            s.size() != 0
+                && matches!(
+                    s.kind(),
+                    SectionKind::Text | SectionKind::Data | SectionKind::ReadOnlyData
+                )
+        })
+        .map(|s| {
+            let name = s.name().unwrap_or_default().to_string();
+            (s.address(), s.address() + s.size(), name)
+        })
+        .collect();
+
+    Ok(sections)
 }
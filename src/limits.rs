@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use crate::{Function, Report};
+
+/// Per-function stack budgets, loaded from `name = max_stack` entries
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Limits {
+    /// Maximum stack by function name
+    pub entries: BTreeMap<String, u64>,
+}
+
+/// Function exceeding its declared stack budget
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitViolation {
+    /// Violating function
+    pub function: Function,
+
+    /// Declared stack budget
+    pub limit: u64,
+}
+
+/// Outcome of checking a report against limits
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LimitCheck {
+    /// Number of listed functions within their budget
+    pub compliant: usize,
+
+    /// Listed functions exceeding their budget
+    pub violations: Vec<LimitViolation>,
+
+    /// Listed functions not found in the report
+    pub missing: Vec<String>,
+}
+
+impl Limits {
+    /// Load limits from a file
+    pub fn load(file: &str) -> Result<Self, anyhow::Error> {
+        let s = std::fs::read_to_string(file)?;
+        Self::parse(&s)
+    }
+
+    /// Parse limits from `name = max_stack` lines, `#` starts a comment
+    pub fn parse(s: &str) -> Result<Self, anyhow::Error> {
+        let mut entries = BTreeMap::new();
+
+        for (i, l) in s.lines().enumerate() {
+            let l = l.trim();
+            if l.is_empty() || l.starts_with('#') {
+                continue;
+            }
+
+            // Names may contain `=` (e.g. associated type bindings), so split on the last
+            let (name, limit) = l
+                .rsplit_once('=')
+                .ok_or_else(|| anyhow::anyhow!("line {}: expected `name = max_stack`", i + 1))?;
+
+            let name = name.trim().trim_matches('"');
+            let limit = limit
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("line {}: invalid stack limit: {}", i + 1, e))?;
+
+            entries.insert(name.to_string(), limit);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Check report functions against their declared limits, unlisted functions are ignored
+    pub fn check(&self, report: &Report) -> LimitCheck {
+        let mut c = LimitCheck::default();
+
+        for (name, limit) in &self.entries {
+            match report.find(name) {
+                Some(f) if f.stack > *limit => c.violations.push(LimitViolation {
+                    function: f.clone(),
+                    limit: *limit,
+                }),
+                Some(_) => c.compliant += 1,
+                None => c.missing.push(name.clone()),
+            }
+        }
+
+        c
+    }
+}
@@ -1,164 +1,2052 @@
-use clap::Parser;
+use std::{
+    collections::HashMap,
+    io::{IsTerminal, Read, Write},
+};
+
+use clap::{Parser, ValueEnum};
 use cli_table::{
     format::{Border, Separator},
-    Cell, Table,
+    Cell, CellStruct, Color, ColorChoice, Style, Table,
 };
-use log::{debug, info, LevelFilter};
+use log::{debug, error, info, warn, LevelFilter};
 
+use rustc_demangle::demangle;
 use whatthestack::*;
 
+/// Output format
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Table of functions
+    Table,
+    /// CSV (`addr,text,stack,name,source`, with diff columns against a baseline)
+    Csv,
+    /// JSON report
+    Json,
+    /// Graphviz call graph (requires call information)
+    Dot,
+    /// Folded stacks for flamegraph tools (`crate;module;function stack`)
+    Folded,
+}
+
+/// Demangled name verbosity
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum DemangleVerbosity {
+    /// Names as demangled
+    Normal,
+    /// Drop lifetimes and simplify const generic values
+    Minimal,
+}
+
+/// Table column selection
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum Column {
+    /// Function address
+    Addr,
+    /// Text size
+    Text,
+    /// Stack size
+    Stack,
+    /// Function name
+    Name,
+    /// Source location (requires `--map-source`)
+    Source,
+    /// Crate name
+    Crate,
+    /// Cumulative stack (requires `--cumulative`)
+    Cumulative,
+    /// Stack region percentage (requires `--stack-region`)
+    Region,
+    /// Stack to text size ratio (requires `--show-ratio`)
+    Ratio,
+    /// Containing section (requires `--show-section`)
+    Section,
+}
+
+impl Column {
+    /// Resolve the column for a table title, the `FRAME` shown with cumulative stack is `Stack`
+    fn from_title(title: &str) -> Option<Self> {
+        match title {
+            "ADDR" => Some(Column::Addr),
+            t if t.starts_with("SIZE") => Some(Column::Text),
+            t if t.starts_with("STACK") => Some(Column::Stack),
+            "FRAME" => Some(Column::Stack),
+            "NAME" => Some(Column::Name),
+            "SOURCE" => Some(Column::Source),
+            "CRATE" => Some(Column::Crate),
+            "CUMULATIVE" => Some(Column::Cumulative),
+            "REGION" => Some(Column::Region),
+            "RATIO" => Some(Column::Ratio),
+            "SECTION" => Some(Column::Section),
+            _ => None,
+        }
+    }
+}
+
+/// Grouping for aggregate views
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    /// Group functions by crate
+    Crate,
+}
+
 /// WhatTheStack (wts), a tool for analysing stack use via LLVM `-Zemit-stack-sizes` information
 #[derive(Clone, Debug, PartialEq, Parser)]
 pub struct Args {
-    /// ELF or object file for parsing
-    pub file: String,
+    /// ELF or object file(s) for parsing (`-` for stdin, or glob patterns such as `target/**/*.o`), multiple files are merged into one report
+    #[cfg_attr(feature = "schema", clap(required_unless_present = "print_schema"))]
+    #[cfg_attr(not(feature = "schema"), clap(required = true))]
+    pub files: Vec<String>,
 
-    /// ELF or object file mode
+    /// ELF or object file mode, `.su` files always use stack usage mode
     #[clap(long, default_value = "elf")]
     pub mode: Mode,
 
-    /// Sort by function or stack size
-    #[clap(long, default_value = "stack")]
-    pub sort: Sort,
+    /// Table columns to display, in the order given (e.g. `name,stack`)
+    #[clap(long, value_delimiter = ',')]
+    pub columns: Option<Vec<Column>>,
+
+    /// Sort keys in priority order (e.g. `stack,text`)
+    #[clap(long, default_value = "stack", value_delimiter = ',')]
+    pub sort: Vec<Sort>,
+
+    /// Sort order, defaults to descending for sizes and ascending for addresses and names
+    #[clap(long)]
+    pub order: Option<Order>,
+
+    /// Sort by an expression over `stack`, `text`, `addr` and `ratio` (e.g. `stack + text`), largest first unless `--order asc`
+    #[clap(long)]
+    pub sort_expr: Option<SortExpr>,
 
     /// Minimum size for filtering
     #[clap(long, default_value = "16")]
     pub min_size: u64,
 
+    /// Maximum size for filtering
+    #[clap(long)]
+    pub max_size: Option<u64>,
+
+    /// Size field used by `--min-size` and `--max-size`
+    #[clap(long, default_value = "stack")]
+    pub size_field: SizeField,
+
     /// Number of lines to show
     #[clap(short = 'n', long, default_value = "10")]
     pub lines: usize,
 
+    /// Policy for combining stack sizes of duplicate functions when merging files
+    #[clap(long, default_value = "max")]
+    pub merge_policy: MergePolicy,
+
+    /// Policy for combining text sizes of duplicate functions when merging files
+    #[clap(long, default_value = "first")]
+    pub merge_text_policy: MergePolicy,
+
+    /// Keep same-named functions from multiple files distinct instead of merging, flagging duplicates
+    #[clap(long)]
+    pub keep_duplicates: bool,
+
+    /// Cross-check text sizes against DWARF derived function sizes
+    #[clap(long)]
+    pub verify_sizes: bool,
+
+    /// Tolerance in bytes for `--verify-sizes` discrepancies
+    #[clap(long, default_value = "0")]
+    pub verify_tolerance: u64,
+
+    /// Expected architecture (e.g. `arm`, `x86_64`), files with a different header architecture are rejected
+    ///
+    /// Used for Thumb address and call handling where the header architecture is unknown,
+    /// pointer width and endianness always come from the header
+    #[clap(long, value_parser = parse_arch)]
+    pub arch: Option<Architecture>,
+
+    /// Override the stack size section name (defaults to `.stack_sizes`)
+    #[clap(long)]
+    pub stack_section: Option<String>,
+
+    /// Cache parsed reports in this directory, keyed by file contents and options
+    #[clap(long)]
+    pub cache_dir: Option<String>,
+
+    /// Bypass the report cache
+    #[clap(long)]
+    pub no_cache: bool,
+
     /// Resolve addresses to source locations
     #[clap(long)]
     pub map_source: bool,
 
+    /// Show the inlined call chain (innermost first) in place of the source location where available
+    #[clap(long, requires = "map_source")]
+    pub inline: bool,
+
+    /// Normalise names, stripping hash suffixes and closure numbering (default)
+    #[clap(long, overrides_with = "no_normalize_names")]
+    pub normalize_names: bool,
+
+    /// Keep names as demangled, without normalisation
+    #[clap(long, overrides_with = "normalize_names")]
+    pub no_normalize_names: bool,
+
+    /// Load DWARF from a separate debug file (e.g. `objcopy --only-keep-debug`) for `--map-source`
+    #[clap(long, requires = "map_source")]
+    pub debug_file: Option<String>,
+
+    /// Maximum width for source locations (defaults to fit the terminal)
+    #[clap(long)]
+    pub source_width: Option<usize>,
+
+    /// Strip generic parameters from displayed names
+    #[clap(long)]
+    pub strip_generics: bool,
+
+    /// Merge entries with identical displayed names, keeping the first (per sort)
+    #[clap(long)]
+    pub dedupe: bool,
+
+    /// Merge functions sharing an address (aliases, weak symbols) into a single entry
+    #[clap(long)]
+    pub dedup: bool,
+
+    /// Show raw symbol addresses, keeping the Thumb bit on ARM targets
+    #[clap(long)]
+    pub raw_addresses: bool,
+
+    /// Demangled name verbosity for display
+    #[clap(long, default_value = "normal")]
+    pub demangle_verbosity: DemangleVerbosity,
+
     /// Disable function name shortening
     #[clap(long)]
     pub long_names: bool,
 
-    /// Filter results by prefix
+    /// Show raw (mangled) symbol names, without demangling or shortening
     #[clap(long)]
-    pub filter: Option<String>,
+    pub raw_names: bool,
 
-    /// Write the generated report to file
+    /// Show addresses as `symbol+0xNN` where they fall within (not at the start of) a symbol
     #[clap(long)]
-    pub write: Option<String>,
+    pub sym_addr: bool,
 
-    /// Load a previously generated report for comparison
+    /// Show worst case cumulative stack (including callees), with the function stack as FRAME
+    #[clap(long)]
+    pub cumulative: bool,
+
+    /// Show stack to text size ratio for each function
+    #[clap(long)]
+    pub show_ratio: bool,
+
+    /// Show each stack frame as a percentage of a stack region (e.g. task stack) of this size
+    #[clap(long)]
+    pub stack_region: Option<u64>,
+
+    /// Percentage of `--stack-region` over which frames are highlighted as warnings
+    #[clap(long, default_value = "50", requires = "stack_region")]
+    pub region_warn: u64,
+
+    /// Show containing section for each function
+    #[clap(long)]
+    pub show_section: bool,
+
+    /// Filter results by section name (e.g. `.text`, `.ramfunc`)
+    #[clap(long)]
+    pub section_filter: Option<String>,
+
+    /// Only include functions reachable from the named entry point (requires call information)
     #[clap(long)]
+    pub reachable_from: Option<String>,
+
+    /// Include compiler builtin and runtime symbols (`__aeabi_*`, `memcpy`, etc.)
+    #[clap(long)]
+    pub include_runtime: bool,
+
+    /// Show only unmangled (`#[no_mangle]` / `extern "C"`) functions
+    #[clap(long)]
+    pub extern_only: bool,
+
+    /// Filter results by name, may be repeated to match any of several values
+    #[clap(long)]
+    pub filter: Vec<String>,
+
+    /// Match `--filter` values as name prefixes or regexes
+    #[clap(long, value_enum, default_value = "prefix")]
+    pub filter_mode: FilterMode,
+
+    /// Minimum function address (inclusive, decimal or `0x` hex)
+    #[clap(long, value_parser = parse_addr)]
+    pub addr_min: Option<u64>,
+
+    /// Maximum function address (inclusive, decimal or `0x` hex)
+    #[clap(long, value_parser = parse_addr)]
+    pub addr_max: Option<u64>,
+
+    /// Exclude functions with names containing this substring, may be repeated
+    ///
+    /// This is applied after `--filter`, so may drop otherwise included functions.
+    #[clap(long)]
+    pub exclude: Vec<String>,
+
+    /// Filter results by regex, combined with prefix and size filters
+    #[clap(long)]
+    pub filter_regex: Option<String>,
+
+    /// Write the generated report to file (`[json|csv|toml|yaml:]path`), may be repeated
+    #[clap(long, value_parser = parse_output)]
+    pub write: Vec<(OutputFormat, String)>,
+
+    /// Sort written reports by name for reproducible output
+    #[clap(long, requires = "write")]
+    pub canonical: bool,
+
+    /// Load a previously generated report for comparison
+    #[clap(long, group = "baseline")]
     pub prev: Option<String>,
 
+    /// Parse an ELF or object file directly and compare it against the input file(s) as baseline (`--compare new.elf old.elf`)
+    #[clap(long, group = "baseline")]
+    pub compare: Option<String>,
+
+    /// Rename prefixes in the baseline report before comparison (`old::=new::`), may be repeated
+    #[clap(long, value_parser = parse_rename)]
+    pub rename: Vec<(String, String)>,
+
+    /// Show separate OLD / NEW / DELTA columns when comparing against a baseline
+    #[clap(long, requires = "baseline")]
+    pub diff_columns: bool,
+
+    /// Show changes as `old → new (+delta, +percent)` when comparing against a baseline
+    #[clap(long, requires = "baseline", conflicts_with = "diff_columns")]
+    pub diff_both: bool,
+
+    /// Show only functions added, removed or changed since the baseline
+    ///
+    /// Added functions are always marked `(new)`, removed functions are listed (marked
+    /// `(removed)`) only with this or `--diff-columns` / `--diff-both`
+    #[clap(long, requires = "baseline")]
+    pub changed_only: bool,
+
+    /// Show only functions with increased stack use since the baseline, with a total
+    #[clap(long, requires = "baseline", conflicts_with = "changed_only")]
+    pub regressions_only: bool,
+
+    /// Also count text size growth as a regression with `--regressions-only`
+    #[clap(long, requires = "regressions_only")]
+    pub regressions_text: bool,
+
+    /// Fail if any function's stack grows by more than this many bytes against the baseline
+    #[clap(long, requires = "baseline")]
+    pub fail_on_growth: Option<u64>,
+
+    /// Write a markdown regression report (diff table, totals and verdict) to file
+    #[clap(long, requires = "baseline")]
+    pub report: Option<String>,
+
+    /// Show an aggregate summary of changes against the baseline instead of the table
+    #[clap(long, requires = "baseline")]
+    pub diff_summary: bool,
+
+    /// Show only the N largest stack regressions against the baseline
+    #[clap(long, requires = "baseline")]
+    pub top_growth: Option<usize>,
+
+    /// Print a sorted JSON address map (`addr`, `size`, `name`) for external symbolizers and exit
+    #[clap(long)]
+    pub dump_symbols: bool,
+
+    /// Print the report fingerprint and exit
+    #[clap(long)]
+    pub fingerprint: bool,
+
+    /// List crates present in the report with function counts and total stack (as `--group-by crate`)
+    #[clap(long)]
+    pub list_crates: bool,
+
+    /// Show total text and stack per crate for the filtered functions, ordered by stack
+    #[clap(long)]
+    pub by_crate: bool,
+
+    /// Show aggregate stack per group instead of per function, ordered by `--sort`
+    #[clap(long)]
+    pub group_by: Option<GroupBy>,
+
+    /// Report the worst case stack call chain from the named root function
+    #[clap(long)]
+    pub max_depth_report: Option<String>,
+
+    /// Write the worst case call chain report to a JSON file
+    #[clap(long, requires = "max_depth_report")]
+    pub max_depth_json: Option<String>,
+
+    /// Check functions against per-function stack budgets (`name = max_stack` lines)
+    #[clap(long)]
+    pub limits: Option<String>,
+
+    /// Exit with an error if any function (or task) exceeds its budget
+    #[clap(long)]
+    pub strict: bool,
+
+    /// Summarise worst case stack per task from `name[:limit] = prefix, ...` assignments
+    #[clap(long)]
+    pub tasks: Option<String>,
+
+    /// List functions fuzzy matching the query, best matches first
+    ///
+    /// Like other listings this applies `--filter`, `--min-size` and runtime exclusion
+    #[clap(long)]
+    pub search: Option<String>,
+
+    /// Print everything known about the named function and exit
+    #[clap(long)]
+    pub explain: Option<String>,
+
+    /// Print the JSON schema for saved reports and exit
+    #[cfg(feature = "schema")]
+    #[clap(long)]
+    pub print_schema: bool,
+
+    /// Stack size (in bytes) over which functions are flagged as warnings
+    #[clap(long)]
+    pub warn: Option<u64>,
+
+    /// Stack size (in bytes) over which functions are flagged as critical
+    #[clap(long)]
+    pub crit: Option<u64>,
+
+    /// Colour stack sizes by `--warn` / `--crit` thresholds and changes by direction
+    #[clap(long, value_enum, default_value = "auto")]
+    pub color: ColorMode,
+
+    /// Prefix names exceeding `--warn` / `--crit` with `!` / `!!` markers
+    #[clap(long)]
+    pub mark: bool,
+
+    /// Show sizes in KiB / MiB units (table output only)
+    #[clap(long)]
+    pub human: bool,
+
+    /// Show a totals row for the listed functions after the table
+    #[clap(long)]
+    pub summary: bool,
+
+    /// List functions with stack frames over this size (in bytes) after the table
+    #[clap(long)]
+    pub frame_warn: Option<u64>,
+
+    /// Fail if any function has a stack frame over this size (in bytes), regardless of filters
+    #[clap(long)]
+    pub max_stack: Option<u64>,
+
+    /// Show stack size distribution (mean, median, p95, max) over all filtered functions after the table
+    #[clap(long)]
+    pub stats: bool,
+
+    /// List referenced functions without definitions or stack information after the table
+    #[clap(long)]
+    pub show_undefined: bool,
+
+    /// Output format
+    #[clap(long, default_value = "table")]
+    pub format: Format,
+
+    /// Write rendered output to a file rather than stdout
+    #[clap(long, short)]
+    pub output: Option<String>,
+
+    /// Disable progress output, also disabled when stdout is not a terminal (e.g. piped or in CI)
+    #[clap(long, short)]
+    pub quiet: bool,
+
     /// Log level
     #[clap(long, default_value = "info")]
     pub log_level: LevelFilter,
 }
 
-fn main() -> anyhow::Result<()> {
-    // Parse arguments
-    let args = Args::parse();
+impl Args {
+    /// Files parsed for the current report, `--compare` replaces the positional inputs
+    fn current_files(&self) -> Vec<String> {
+        match &self.compare {
+            Some(c) => vec![c.clone()],
+            None => self.files.clone(),
+        }
+    }
+
+    /// Sort keys with their order, defaulting per key
+    fn sort_keys(&self) -> Vec<(Sort, Order)> {
+        self.sort
+            .iter()
+            .map(|s| (s.clone(), self.order.unwrap_or(s.default_order())))
+            .collect()
+    }
 
-    // Setup logging
-    let _ = simplelog::SimpleLogger::init(args.log_level, Default::default());
+    /// Whether removed functions are listed alongside current functions, for table and CSV output
+    fn lists_removed(&self) -> bool {
+        (self.changed_only || self.diff_columns || self.diff_both)
+            && !self.regressions_only
+            && matches!(self.format, Format::Table | Format::Csv)
+    }
 
-    debug!("args: {:?}", args);
+    /// Name of the baseline used for comparison
+    fn baseline(&self) -> Option<String> {
+        match (&self.prev, &self.compare) {
+            (Some(p), _) => Some(p.clone()),
+            (_, Some(_)) => Some(self.files.join(", ")),
+            _ => None,
+        }
+    }
+}
 
-    // Load ELF file
-    let mut report = Report::parse(&args.file, args.mode, args.map_source)?;
+/// Colour output mode
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum ColorMode {
+    /// Colour when writing to a terminal
+    #[default]
+    Auto,
+    /// Always colour, including when piped or writing to file
+    Always,
+    /// Never colour
+    Never,
+}
 
-    if args.write.is_some() && args.prev.is_some() {
-        return Err(anyhow::anyhow!(
-            "cannot write and compare reports at the same time"
-        ));
+/// Rendered output destination
+struct Output {
+    w: Box<dyn Write>,
+    color: bool,
+}
+
+impl Output {
+    /// Print a table in the borderless display style
+    fn table(&mut self, data: Vec<Vec<CellStruct>>, titles: Vec<&str>) -> std::io::Result<()> {
+        let color = match self.color {
+            true => ColorChoice::Always,
+            false => ColorChoice::Never,
+        };
+        let table = data
+            .table()
+            .title(titles)
+            .border(Border::builder().build())
+            .separator(Separator::builder().row(None).build())
+            .color_choice(color);
+
+        writeln!(self, "{}", table.display()?)
     }
+}
 
-    // Write report if enabled
-    if let Some(f) = args.write {
-        info!("Saving report to: {}", f);
-        report.save(&f)?;
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.w.write(buf)
     }
 
-    // Load report for comparison if enabled
-    let prev = match &args.prev {
-        Some(f) => Report::load(f).map(|v| Some(v))?,
-        None => None,
-    };
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.w.flush()
+    }
+}
 
-    // Apply sort
-    report.sort(args.sort);
+/// Parse an output as `format:path`, inferring the format from the extension if not specified
+fn parse_output(s: &str) -> Result<(OutputFormat, String), String> {
+    if let Some((f, path)) = s.split_once(':') {
+        if let Ok(format) = OutputFormat::from_str(f, true) {
+            return Ok((format, path.to_string()));
+        }
+    }
+    Ok((OutputFormat::from_path(s), s.to_string()))
+}
 
-    let mut defined = report.functions.clone();
+/// Format a change as `old → new (+delta, +percent)`, or `new (+delta)` for new functions
+fn format_both(old: Option<u64>, new: u64, human: bool) -> String {
+    let size = |v: u64| format_size(v, human);
+    match old {
+        Some(o) if o == new => size(new),
+        Some(0) => format!(
+            "0 → {} ({}, new)",
+            size(new),
+            format_delta(new as i64, human)
+        ),
+        Some(o) => {
+            let d = new as i64 - o as i64;
+            let pct = d as f64 * 100.0 / o as f64;
+            format!(
+                "{} → {} ({}, {:+.0}%)",
+                size(o),
+                size(new),
+                format_delta(d, human),
+                pct
+            )
+        }
+        None => format!("{} ({})", size(new), format_delta(new as i64, human)),
+    }
+}
 
-    if defined.len() == 0 {
-        return Err(anyhow::anyhow!("no stack length information found"));
+/// Colour for a stack severity
+fn severity_color(s: Severity) -> Option<Color> {
+    match s {
+        Severity::Crit => Some(Color::Red),
+        Severity::Warn => Some(Color::Yellow),
+        Severity::Normal => None,
     }
+}
 
-    // Apply filter if requested
-    if let Some(s) = args.filter {
-        defined = defined
-            .drain(..)
-            .filter(|f| f.name.starts_with(&s))
-            .collect();
+/// Colour for a size change, red for growth and green for reductions
+fn delta_color(d: i64) -> Option<Color> {
+    match d.cmp(&0) {
+        std::cmp::Ordering::Greater => Some(Color::Red),
+        std::cmp::Ordering::Less => Some(Color::Green),
+        std::cmp::Ordering::Equal => None,
     }
+}
 
-    // Build table for display
-    let n = defined.len().min(args.lines);
-    let table_data: Vec<_> = (&defined[..n])
-        .iter()
-        .map(|f| {
-            // Truncate name
-            let name = match args.long_names {
-                true => f.name.clone(),
-                false => compress_name(&f.name),
-            };
+/// Format a size for display, using KiB / MiB units if enabled
+fn format_size(v: u64, human: bool) -> String {
+    match human {
+        true => human_size(v),
+        false => v.to_string(),
+    }
+}
 
-            // Compute diffs if we have a previous report
-            let diffs = match prev.as_ref().map(|p| p.find(&f.name)).flatten() {
-                Some(f1) => Some((
-                    f.text as i64 - f1.text as i64,
-                    f.stack as i64 - f1.stack as i64,
-                )),
-                None => None,
-            };
+/// Format a signed size change for display, using KiB / MiB units if enabled
+fn format_delta(d: i64, human: bool) -> String {
+    match (human, d < 0) {
+        (true, true) => format!("-{}", human_size(d.unsigned_abs())),
+        (true, false) => format!("+{}", human_size(d as u64)),
+        (false, _) => format!("{:+}", d),
+    }
+}
 
-            // Setup display line
-            let mut line = vec![format!("0x{:08x}", f.addr).cell()];
+/// Select entries from a table row (or titles) by index, in the provided order
+fn select_columns<T>(row: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut row: Vec<_> = row.into_iter().map(Some).collect();
+    order.iter().filter_map(|i| row[*i].take()).collect()
+}
 
-            match diffs {
-                Some((d_text, d_stack)) => {
-                    line.push(format!("{:<4} ({:+})", f.text, d_text).cell());
-                    line.push(format!("{:<4} ({:+})", f.stack, d_stack).cell());
-                }
-                None => {
-                    line.push(f.text.cell());
-                    line.push(f.stack.cell());
-                }
-            }
+/// Render a markdown regression report for a baseline comparison
+fn regression_report(
+    args: &Args,
+    baseline: &str,
+    report: &Report,
+    prev: &Report,
+    diff: &Diff,
+    regressions: &[&FunctionChange],
+) -> String {
+    let mut md = String::from("# Stack regression report\n\n");
 
-            line.push(name.cell());
+    // Verdict
+    let verdict = match (args.fail_on_growth, regressions.is_empty()) {
+        (None, _) => "not checked (no `--fail-on-growth` limit)".to_string(),
+        (Some(l), true) => format!("**PASS** (no function grew by more than {} bytes)", l),
+        (Some(l), false) => format!(
+            "**FAIL** ({} functions grew by more than {} bytes)",
+            regressions.len(),
+            l
+        ),
+    };
+    md += &format!("Verdict: {}\n\n", verdict);
 
-            // Add source location if enabled
-            if args.map_source {
-                line.push(f.source.clone().cell());
-            }
+    // Input metadata
+    md += "| | File | Functions | Fingerprint |\n|---|---|---|---|\n";
+    md += &format!(
+        "| Baseline | `{}` | {} | `{:016x}` |\n",
+        baseline,
+        prev.functions.len(),
+        prev.fingerprint()
+    );
+    md += &format!(
+        "| Current | `{}` | {} | `{:016x}` |\n\n",
+        args.current_files().join("`, `"),
+        report.functions.len(),
+        report.fingerprint()
+    );
 
-            line
-        })
+    // Totals
+    let s = DiffSummary::from_diff(diff, report, prev);
+    md += "## Totals\n\n";
+    md += &format!("- Total stack delta: {:+} bytes\n", s.total_stack_delta);
+    md += &format!("- Functions grown: {}, shrunk: {}\n", s.grew, s.shrank);
+    if let Some((name, d)) = &s.largest_regression {
+        md += &format!("- Largest regression: `{}` ({:+})\n", name, d);
+    }
+    md += "\n";
+
+    // Changed functions, largest growth first
+    let mut changed: Vec<_> = diff
+        .added
+        .iter()
+        .chain(&diff.changed)
+        .chain(&diff.removed)
+        .filter(|c| c.stack.kind() != DeltaKind::Unchanged)
         .collect();
+    changed.sort_by_key(|c| std::cmp::Reverse(c.stack.value()));
 
-    let mut titles = vec!["ADDR", "SIZE", "STACK", "NAME"];
-    if args.map_source {
-        titles.push("SOURCE");
+    md += "## Changes\n\n";
+    if changed.is_empty() {
+        md += "No stack changes.\n";
+        return md;
+    }
+
+    md += "| Stack | Delta | Name |\n|---:|---:|---|\n";
+    for c in changed {
+        let stack = match (c.stack.old, c.stack.new) {
+            (None, Some(n)) => format!("{} (new)", n),
+            (Some(o), None) => format!("{} (removed)", o),
+            (o, n) => format!("{} → {}", o.unwrap_or(0), n.unwrap_or(0)),
+        };
+        md += &format!(
+            "| {} | {:+} | `{}` |\n",
+            stack,
+            c.stack.value(),
+            c.function.name.replace('|', "\\|")
+        );
+    }
+
+    md
+}
+
+/// Report functions over the stack budget, failing if any are found
+fn check_budget(over: &[Function], limit: u64) -> anyhow::Result<()> {
+    if over.is_empty() {
+        return Ok(());
+    }
+
+    for f in over {
+        error!("{} bytes: {}", f.stack, f.name);
+    }
+
+    Err(anyhow::anyhow!(
+        "{} functions exceed stack budget of {} bytes",
+        over.len(),
+        limit
+    ))
+}
+
+/// Report functions over the allowed stack growth, failing if any are found
+fn check_growth(regressions: &[&FunctionChange]) -> anyhow::Result<()> {
+    if regressions.is_empty() {
+        return Ok(());
+    }
+
+    for c in regressions {
+        warn!(
+            "Stack regression: {} ({:+})",
+            c.function.name,
+            c.stack.value()
+        );
+    }
+
+    Err(anyhow::anyhow!(
+        "{} functions exceed the allowed stack growth",
+        regressions.len()
+    ))
+}
+
+/// Read and parse an input file (or stdin for `-`), through the report cache if enabled
+///
+/// `.su` files are loaded as stack usage for combining with ELF reports, other files
+/// return their data for symbol lookups and size checks.
+fn load_input(
+    args: &Args,
+    f: &str,
+    opts: &ParseOptions,
+) -> anyhow::Result<(Report, Option<Vec<u8>>)> {
+    let d = read_input(f)?;
+
+    let opts = match f.ends_with(".su") {
+        true => opts.clone().mode(Mode::StackUsage),
+        false => opts.clone(),
+    };
+
+    let r = match (&args.cache_dir, args.no_cache) {
+        (Some(c), false) => Report::parse_cached(&d, &opts, c)?,
+        _ => Report::parse_bytes_with(&d, &opts)?,
+    };
+
+    match opts.mode {
+        Mode::StackUsage => Ok((r, None)),
+        _ => Ok((r, Some(d))),
+    }
+}
+
+/// Read an input file, or stdin for `-`
+fn read_input(f: &str) -> anyhow::Result<Vec<u8>> {
+    if f != "-" {
+        return std::fs::read(f).map_err(|e| anyhow::anyhow!("failed to read '{}': {}", f, e));
+    }
+
+    let mut d = vec![];
+    std::io::stdin().read_to_end(&mut d)?;
+    match d.is_empty() {
+        true => Err(anyhow::anyhow!("no input data on stdin")),
+        false => Ok(d),
+    }
+}
+
+/// Expand glob patterns (e.g. `target/**/*.o`) in input file arguments
+///
+/// Patterns are expanded in path order, failing where nothing matches.
+fn expand_inputs(files: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut expanded = vec![];
+
+    for f in files {
+        if f == "-" || !f.contains(['*', '?', '[']) {
+            expanded.push(f.clone());
+            continue;
+        }
+
+        let mut matches = glob::glob(f)
+            .map_err(|e| anyhow::anyhow!("invalid pattern '{}': {}", f, e))?
+            .collect::<Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            return Err(anyhow::anyhow!("no files match '{}'", f));
+        }
+        matches.sort();
+
+        debug!("Pattern {} matched {} files", f, matches.len());
+        expanded.extend(matches.iter().map(|p| p.to_string_lossy().to_string()));
+    }
+
+    Ok(expanded)
+}
+
+/// Parse address arguments
+fn parse_addr(s: &str) -> Result<u64, String> {
+    parse_u64(s).ok_or_else(|| format!("invalid address '{}'", s))
+}
+
+/// Parse architecture arguments
+fn parse_arch(s: &str) -> Result<Architecture, String> {
+    parse_architecture(s).ok_or_else(|| format!("unknown architecture '{}'", s))
+}
+
+/// Parse `old=new` rename arguments
+fn parse_rename(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((from, to)) => Ok((from.to_string(), to.to_string())),
+        None => Err(format!("invalid rename '{}', expected `old=new`", s)),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    // Parse arguments
+    let args = Args::parse();
+
+    // Setup logging, to stderr so output may be redirected
+    let _ = simplelog::WriteLogger::init(args.log_level, Default::default(), std::io::stderr());
+
+    // Setup output, defaulting to stdout
+    let mut out = match &args.output {
+        Some(f) => Output {
+            w: Box::new(std::fs::File::create(f)?),
+            color: args.color == ColorMode::Always,
+        },
+        None => Output {
+            w: Box::new(std::io::stdout()),
+            color: match args.color {
+                ColorMode::Auto => std::io::stdout().is_terminal(),
+                ColorMode::Always => true,
+                ColorMode::Never => false,
+            },
+        },
+    };
+
+    debug!("args: {:?}", args);
+
+    // Check selected columns are available
+    if let Some(c) = &args.columns {
+        for (column, enabled, flag) in [
+            (Column::Source, args.map_source, "--map-source"),
+            (Column::Cumulative, args.cumulative, "--cumulative"),
+            (
+                Column::Region,
+                args.stack_region.is_some(),
+                "--stack-region",
+            ),
+            (Column::Ratio, args.show_ratio, "--show-ratio"),
+            (Column::Section, args.show_section, "--show-section"),
+        ] {
+            if c.contains(&column) && !enabled {
+                let name = column.to_possible_value().unwrap();
+                return Err(anyhow::anyhow!(
+                    "the `{}` column requires `{}`",
+                    name.get_name(),
+                    flag
+                ));
+            }
+        }
     }
 
-    let table = table_data
-        .table()
-        .title(titles)
-        .border(Border::builder().build())
-        .separator(Separator::builder().row(None).build());
+    // Print report schema if requested
+    #[cfg(feature = "schema")]
+    if args.print_schema {
+        writeln!(out, "{}", Report::schema()?)?;
+        return Ok(());
+    }
 
-    let table_display = table.display().unwrap();
-    println!("{}", table_display);
+    // Select name transform for display
+    // Raw names are left as-is, name shortening assumes Rust path syntax
+    let mut transform: Box<dyn NameTransform> = match args.long_names || args.raw_names {
+        true => Box::new(LongNames),
+        false => Box::new(CompressNames),
+    };
+    if args.strip_generics && !args.raw_names {
+        transform = Box::new(move |n: &str| transform.transform(&strip_generics(n)));
+    }
+    if args.demangle_verbosity == DemangleVerbosity::Minimal && !args.raw_names {
+        transform = Box::new(move |n: &str| transform.transform(&simplify_name(n)));
+    }
+
+    // Setup parse options
+    let mut opts = ParseOptions::new()
+        .mode(args.mode.clone())
+        .map_source(args.map_source)
+        .progress(!args.quiet && std::io::stdout().is_terminal())
+        .normalize_names(!args.no_normalize_names)
+        .raw_names(args.raw_names)
+        .dedup(args.dedup)
+        .raw_addresses(args.raw_addresses);
+    if let Some(s) = &args.stack_section {
+        opts = opts.stack_section(s);
+    }
+    if let Some(a) = args.arch {
+        opts = opts.arch(a);
+    }
+    if let Some(f) = &args.debug_file {
+        opts = opts.debug_file(f);
+    }
+
+    // Load ELF file(s)
+    let mut reports = vec![];
+    let mut inputs = vec![];
+    for f in &expand_inputs(&args.current_files())? {
+        let (r, data) = load_input(&args, f, &opts)?;
+
+        // Cross-check sizes against DWARF if enabled
+        if let (true, Some(d)) = (args.verify_sizes, &data) {
+            let mismatches = r.verify_sizes(d, args.verify_tolerance)?;
+            for m in &mismatches {
+                warn!(
+                    "Size mismatch for {} (0x{:08x}): text {} dwarf {}",
+                    m.function.name, m.function.addr, m.function.text, m.dwarf
+                );
+            }
+            info!("{}: {} size discrepancies found", f, mismatches.len());
+        }
+
+        // Keep ELF / object data for symbol lookups
+        inputs.extend(data);
+        reports.push(r);
+    }
+
+    // Merge reports when multiple files are provided
+    let mut report = match reports.len() {
+        0 => return Err(anyhow::anyhow!("no input file specified")),
+        1 => reports.remove(0),
+        _ if args.keep_duplicates => Report::concat(reports),
+        _ => Report::merge(reports, args.merge_text_policy, args.merge_policy),
+    };
+
+    if !args.write.is_empty() && args.prev.is_some() {
+        return Err(anyhow::anyhow!(
+            "cannot write and compare reports at the same time"
+        ));
+    }
+
+    // Write reports if enabled
+    for (format, f) in &args.write {
+        info!("Saving report to: {}", f);
+        report.save_as(f, *format, args.canonical)?;
+    }
+
+    // Load report for comparison if enabled
+    let mut prev = match (&args.prev, &args.compare) {
+        (Some(f), _) => Report::load(f).map(Some)?,
+        (_, Some(_)) => {
+            // Debug files apply only to the current binary
+            let opts = ParseOptions {
+                debug_file: None,
+                ..opts.clone()
+            };
+            let baseline = expand_inputs(&args.files)?
+                .iter()
+                .map(|f| load_input(&args, f, &opts).map(|(r, _)| r))
+                .collect::<Result<Vec<_>, _>>()?;
+            match baseline.len() {
+                1 => baseline.into_iter().next(),
+                _ => Some(Report::merge(
+                    baseline,
+                    args.merge_text_policy,
+                    args.merge_policy,
+                )),
+            }
+        }
+        _ => None,
+    };
+
+    // Apply normalisation and renames to previous report for matching
+    if let Some(p) = prev.as_mut() {
+        if opts.normalize_names && !opts.raw_names {
+            for f in p.functions.iter_mut() {
+                f.name = normalize_name(&f.name);
+            }
+        }
+        for (from, to) in &args.rename {
+            p.rename_prefix(from, to);
+        }
+    }
+
+    // Compare against the baseline (prior to pruning and filtering), all diff output renders from this
+    let diff = prev.as_ref().map(|p| report.compare(p));
+
+    // Collect growth over the allowed limit and write regression report if enabled
+    let mut regressions = vec![];
+    if let (Some(p), Some(d)) = (&prev, &diff) {
+        if let Some(limit) = args.fail_on_growth {
+            regressions = d
+                .regressions(false)
+                .into_iter()
+                .filter(|c| c.stack.value() > limit as i64)
+                .collect();
+        }
+
+        if let Some(f) = &args.report {
+            let baseline = args.baseline().unwrap();
+            let md = regression_report(&args, &baseline, &report, p, d, &regressions);
+            info!("Saving regression report to: {}", f);
+            std::fs::write(f, md)?;
+        }
+    }
+
+    // Prune functions unreachable from the entry point if requested
+    if let Some(root) = &args.reachable_from {
+        let n = report.prune_unreachable(root)?;
+        info!("Pruned {} functions unreachable from {}", n, root);
+    }
+
+    // Apply sort
+    match &args.sort_expr {
+        Some(e) => report.sort_by_expr(e, args.order.unwrap_or(Order::Desc)),
+        None => report.sort_keys(&args.sort_keys()),
+    }
+
+    if report.functions.is_empty() {
+        return Err(anyhow::anyhow!("no stack length information found"));
+    }
+
+    // Collect functions over the stack budget (prior to filtering) if enabled
+    let mut over_budget: Vec<Function> = match args.max_stack {
+        Some(limit) => report
+            .functions
+            .iter()
+            .filter(|f| f.stack > limit)
+            .cloned()
+            .collect(),
+        None => vec![],
+    };
+    over_budget.sort_by_key(|f| std::cmp::Reverse(f.stack));
+
+    // Show the selected output mode
+    let ctx = Context {
+        args: &args,
+        transform: transform.as_ref(),
+        prev: prev.as_ref(),
+        diff: diff.as_ref(),
+        inputs: &inputs,
+    };
+    show(&ctx, &mut out, report)?;
+
+    // Fail on functions over the stack budget or allowed growth, for all modes
+    check_budget(&over_budget, args.max_stack.unwrap_or(0))?;
+    check_growth(&regressions)
+}
+
+/// Options and inputs shared by output modes
+struct Context<'a> {
+    args: &'a Args,
+    transform: &'a dyn NameTransform,
+    prev: Option<&'a Report>,
+    diff: Option<&'a Diff>,
+    inputs: &'a [Vec<u8>],
+}
+
+/// Show the selected output mode
+///
+/// Call graph and whole-report modes use all functions, listings use the filtered report
+fn show(ctx: &Context, out: &mut Output, mut report: Report) -> anyhow::Result<()> {
+    let (args, prev, diff) = (ctx.args, ctx.prev, ctx.diff);
+
+    // Modes over the full report
+    if let (Some(p), Some(d), true) = (prev, diff, args.diff_summary) {
+        return show_diff_summary(out, &report, p, d);
+    }
+    if args.dump_symbols {
+        return dump_symbols(out, &report);
+    }
+    if args.fingerprint {
+        writeln!(out, "{:016x}", report.fingerprint())?;
+        return Ok(());
+    }
+    if let Some(root) = &args.max_depth_report {
+        return show_call_chain(ctx, out, &report, root);
+    }
+    if let Some(file) = &args.limits {
+        return show_limits(ctx, out, &report, file);
+    }
+    if let Some(file) = &args.tasks {
+        return show_tasks(ctx, out, &report, file);
+    }
+    if let Some(name) = &args.explain {
+        return explain(ctx, out, &report, name);
+    }
+    if args.format == Format::Dot {
+        return show_dot(ctx, out, &report);
+    }
+
+    // Compute cumulative stack over all functions (prior to filtering) if enabled
+    let cumulative: HashMap<String, u64> = match args.cumulative {
+        true => report
+            .max_depth()
+            .into_iter()
+            .map(|(f, d)| (f.name, d))
+            .collect(),
+        false => HashMap::new(),
+    };
+    if args.cumulative && report.functions.iter().all(|f| f.calls.is_empty()) {
+        warn!("No call information found, link with `--emit-relocs` to enable call graph analysis");
+    }
+
+    // Warn on frames that alone exceed the stack region
+    if let Some(region) = args.stack_region {
+        for f in report.functions.iter().filter(|f| f.stack > region) {
+            warn!(
+                "{} uses {} bytes, exceeding the {} byte stack region",
+                f.name, f.stack, region
+            );
+        }
+    }
+
+    // Apply filters shared by all listings
+    apply_filters(ctx, &mut report)?;
+
+    // Show only changed functions if enabled
+    if let (Some(d), true) = (diff, args.changed_only) {
+        let changed: std::collections::HashSet<&str> = d
+            .added
+            .iter()
+            .chain(&d.changed)
+            .map(|c| c.function.name.as_str())
+            .collect();
+        report.retain(|f| changed.contains(f.name.as_str()));
+    }
+
+    // List removed functions if requested, through the same filters, these can never be regressions
+    if let (Some(d), true) = (diff, args.lists_removed()) {
+        let mut removed = Report {
+            functions: d.removed.iter().map(|c| c.function.clone()).collect(),
+            ..Default::default()
+        };
+        apply_filters(ctx, &mut removed)?;
+        report.functions.extend(removed.functions);
+    }
+
+    // Show only regressed functions if enabled, tracking stack and text growth for the totals
+    let mut regressions = std::collections::HashMap::new();
+    if let (Some(d), true) = (diff, args.regressions_only) {
+        regressions = d
+            .regressions(args.regressions_text)
+            .into_iter()
+            .map(|c| (c.function.name.clone(), (c.stack.value(), c.text.value())))
+            .collect();
+        report.retain(|f| regressions.contains_key(&f.name));
+    }
+
+    // Sort the listed functions, by cumulative stack in place of stack if enabled
+    match &args.sort_expr {
+        Some(e) => report.sort_by_expr(e, args.order.unwrap_or(Order::Desc)),
+        None if args.cumulative => report.sort_cumulative(&args.sort_keys(), &cumulative),
+        None => report.sort_keys(&args.sort_keys()),
+    }
+
+    // Listings over the filtered report
+    if args.by_crate {
+        return show_by_crate(out, &report);
+    }
+    if args.list_crates || args.group_by == Some(GroupBy::Crate) {
+        return show_crates(args, out, &report);
+    }
+    if let Some(q) = &args.search {
+        return show_search(ctx, out, &report, q);
+    }
+    if let (Some(d), Some(n)) = (diff, args.top_growth) {
+        return show_top_growth(ctx, out, &report, d, n);
+    }
+    if matches!(args.format, Format::Csv | Format::Json | Format::Folded) {
+        return write_report(ctx, out, report.functions);
+    }
+
+    show_table(ctx, out, report, &cumulative, &regressions)
+}
+
+/// Apply runtime, ABI, name, size, address and section filters
+fn apply_filters(ctx: &Context, report: &mut Report) -> anyhow::Result<()> {
+    let (args, transform) = (ctx.args, ctx.transform);
+
+    // Exclude runtime symbols unless requested
+    if !args.include_runtime {
+        report.retain(|f| !is_runtime(&f.name));
+    }
+
+    // Show only extern functions if requested
+    if args.extern_only {
+        report.retain(|f| f.abi == Abi::Extern);
+    }
+
+    // Build and apply filters
+    let mut filters = FilterSet::new().min_size(args.size_field, args.min_size);
+    for p in &args.filter {
+        filters = filters.name(args.filter_mode, p)?;
+    }
+    for e in &args.exclude {
+        filters = filters.exclude(e);
+    }
+    if let Some(r) = &args.filter_regex {
+        filters = filters.regex(r)?;
+    }
+    if let Some(m) = args.max_size {
+        filters = filters.max_size(args.size_field, m);
+    }
+    if let Some(a) = args.addr_min {
+        filters = filters.min_addr(a);
+    }
+    if let Some(a) = args.addr_max {
+        filters = filters.max_addr(a);
+    }
+    report.retain(|f| filters.matches(f));
+
+    // Apply section filter if requested
+    if let Some(s) = &args.section_filter {
+        report.retain(|f| &f.section == s);
+    }
+
+    // Merge entries with identical display names if enabled
+    if args.dedupe {
+        let mut seen = std::collections::HashSet::new();
+        report.retain(|f| seen.insert(f.display_name(transform)));
+    }
+
+    Ok(())
+}
+
+/// Show the distribution of changes against the baseline
+fn show_diff_summary(
+    out: &mut Output,
+    report: &Report,
+    prev: &Report,
+    diff: &Diff,
+) -> anyhow::Result<()> {
+    let s = DiffSummary::from_diff(diff, report, prev);
+
+    writeln!(out, "Total stack delta: {:+} bytes", s.total_stack_delta)?;
+    writeln!(out, "Functions grown: {}, shrunk: {}", s.grew, s.shrank)?;
+    if let Some((name, d)) = &s.largest_regression {
+        writeln!(out, "Largest regression: {} ({:+})", name, d)?;
+    }
+    for (i, p) in ["p50", "p90", "p99"].iter().enumerate() {
+        writeln!(
+            out,
+            "{}: {} -> {}",
+            p, s.prev_percentiles[i], s.current_percentiles[i]
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Dump the address map as JSON
+fn dump_symbols(out: &mut Output, report: &Report) -> anyhow::Result<()> {
+    #[derive(serde::Serialize)]
+    struct Symbol {
+        addr: u64,
+        size: u64,
+        name: String,
+    }
+
+    let sizes: HashMap<_, _> = report.functions.iter().map(|f| (f.addr, f.text)).collect();
+    let symbols: Vec<_> = report
+        .address_map()
+        .into_iter()
+        .map(|(addr, name)| Symbol {
+            addr,
+            size: sizes[&addr],
+            name,
+        })
+        .collect();
+
+    writeln!(out, "{}", serde_json::to_string_pretty(&symbols)?)?;
+
+    Ok(())
+}
+
+/// Show per-crate totals
+fn show_by_crate(out: &mut Output, report: &Report) -> anyhow::Result<()> {
+    let table_data: Vec<_> = report
+        .group_by_crate()
+        .into_iter()
+        .map(|(name, text, stack)| vec![name.cell(), text.cell(), stack.cell()])
+        .collect();
+
+    out.table(table_data, vec!["CRATE", "SIZE", "STACK"])?;
+
+    Ok(())
+}
+
+/// List crates with function counts and totals
+fn show_crates(args: &Args, out: &mut Output, report: &Report) -> anyhow::Result<()> {
+    let (sort, order) = &args.sort_keys()[0];
+    let crates = report.crates_sorted(sort, *order);
+
+    let table_data: Vec<_> = crates
+        .iter()
+        .map(|(name, (count, text, stack))| {
+            vec![name.cell(), count.cell(), text.cell(), stack.cell()]
+        })
+        .collect();
+
+    out.table(table_data, vec!["CRATE", "FUNCTIONS", "SIZE", "STACK"])?;
+
+    Ok(())
+}
+
+/// Show the worst case call chain from a root function
+fn show_call_chain(
+    ctx: &Context,
+    out: &mut Output,
+    report: &Report,
+    root: &str,
+) -> anyhow::Result<()> {
+    let (args, transform) = (ctx.args, ctx.transform);
+
+    let (frames, recursive) = report
+        .worst_path(root)
+        .ok_or_else(|| anyhow::anyhow!("root function '{}' not found", root))?;
+
+    if let Some(f) = &args.max_depth_json {
+        info!("Saving call chain report to: {}", f);
+        std::fs::write(f, serde_json::to_string_pretty(&frames)?)?;
+    }
+
+    let table_data: Vec<_> = frames
+        .iter()
+        .map(|f| {
+            let mut line = vec![
+                f.function.stack.cell(),
+                f.cumulative.cell(),
+                f.function.display_name(transform).cell(),
+            ];
+            if args.map_source {
+                line.push(f.function.source.clone().cell());
+            }
+            line
+        })
+        .collect();
+
+    let mut titles = vec!["STACK", "CUMULATIVE", "NAME"];
+    if args.map_source {
+        titles.push("SOURCE");
+    }
+
+    out.table(table_data, titles)?;
+
+    if frames.iter().all(|f| f.function.calls.is_empty()) {
+        warn!("No call information found, link with `--emit-relocs` to enable call graph analysis");
+    }
+    if recursive {
+        warn!("Recursion detected, worst case stack may be unbounded");
+    }
+
+    Ok(())
+}
+
+/// Check functions against per-function stack limits
+fn show_limits(ctx: &Context, out: &mut Output, report: &Report, file: &str) -> anyhow::Result<()> {
+    let (args, transform) = (ctx.args, ctx.transform);
+
+    let check = Limits::load(file)?.check(report);
+
+    if !check.violations.is_empty() {
+        let table_data: Vec<_> = check
+            .violations
+            .iter()
+            .map(|v| {
+                vec![
+                    v.function.stack.cell(),
+                    v.limit.cell(),
+                    format!("+{}", v.function.stack - v.limit).cell(),
+                    v.function.display_name(transform).cell(),
+                ]
+            })
+            .collect();
+
+        out.table(table_data, vec!["STACK", "LIMIT", "OVER", "NAME"])?;
+    }
+
+    for name in &check.missing {
+        warn!("Function with limit not found: {}", name);
+    }
+
+    info!(
+        "{} functions compliant, {} violating",
+        check.compliant,
+        check.violations.len()
+    );
+
+    if args.strict && !check.violations.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} functions exceed their stack limits",
+            check.violations.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Summarise per-task stack use
+fn show_tasks(ctx: &Context, out: &mut Output, report: &Report, file: &str) -> anyhow::Result<()> {
+    let (args, transform) = (ctx.args, ctx.transform);
+
+    let summaries = Tasks::load(file)?.summarise(report);
+
+    let table_data: Vec<_> = summaries
+        .iter()
+        .map(|t| {
+            let status = match (t.limit, t.over_limit()) {
+                (None, _) => "-",
+                (_, true) => "OVER",
+                (_, false) => "OK",
+            };
+            vec![
+                t.name.clone().cell(),
+                t.functions.cell(),
+                t.worst.cell(),
+                t.limit
+                    .map(|l| l.to_string())
+                    .unwrap_or("-".to_string())
+                    .cell(),
+                status.cell(),
+                t.worst_function
+                    .as_ref()
+                    .map(|n| transform.transform(n))
+                    .unwrap_or_default()
+                    .cell(),
+            ]
+        })
+        .collect();
+
+    out.table(
+        table_data,
+        vec!["TASK", "FUNCTIONS", "WORST", "LIMIT", "STATUS", "ENTRY"],
+    )?;
+
+    if report.functions.iter().all(|f| f.calls.is_empty()) {
+        warn!("No call information found, task stack excludes callees (link with `--emit-relocs`)");
+    }
+
+    let over = summaries.iter().filter(|t| t.over_limit()).count();
+    if args.strict && over > 0 {
+        return Err(anyhow::anyhow!("{} tasks exceed their stack limits", over));
+    }
+
+    Ok(())
+}
+
+/// Search for functions by fuzzy name match
+fn show_search(ctx: &Context, out: &mut Output, report: &Report, q: &str) -> anyhow::Result<()> {
+    let (args, transform) = (ctx.args, ctx.transform);
+
+    let matches = report.find_fuzzy(q);
+
+    let table_data: Vec<_> = matches
+        .iter()
+        .take(args.lines)
+        .map(|(f, _)| {
+            vec![
+                format!("0x{:08x}", f.addr).cell(),
+                f.text.cell(),
+                f.stack.cell(),
+                f.display_name(transform).cell(),
+            ]
+        })
+        .collect();
+
+    out.table(table_data, vec!["ADDR", "SIZE", "STACK", "NAME"])?;
+
+    if matches.len() > args.lines {
+        info!("Truncated {} matches", matches.len() - args.lines);
+    }
+
+    Ok(())
+}
+
+/// Show function details and callees
+fn explain(ctx: &Context, out: &mut Output, report: &Report, name: &str) -> anyhow::Result<()> {
+    let transform = ctx.transform;
+
+    let f = report
+        .find(name)
+        .or_else(|| report.functions.iter().find(|f| f.mangled == name))
+        .ok_or_else(|| anyhow::anyhow!("function '{}' not found", name))?;
+
+    writeln!(out, "Name: {}", f.name)?;
+    if !f.mangled.is_empty() {
+        writeln!(out, "Mangled: {}", f.mangled)?;
+    }
+    if f.abi == Abi::Extern {
+        writeln!(out, "ABI: extern")?;
+    }
+    writeln!(out, "Address: 0x{:08x}", f.addr)?;
+    writeln!(out, "Text: {} bytes", f.text)?;
+    writeln!(out, "Stack: {} bytes", f.stack)?;
+    if !f.section.is_empty() {
+        writeln!(out, "Section: {}", f.section)?;
+    }
+    if !f.source.is_empty() {
+        writeln!(out, "Source: {}", f.source)?;
+    }
+    if !f.inline.is_empty() {
+        writeln!(out, "Inlined (innermost first):")?;
+        for frame in &f.inline {
+            writeln!(out, "  {}", frame)?;
+        }
+    }
+
+    if f.calls.is_empty() {
+        writeln!(
+            out,
+            "Calls: none found (link with `--emit-relocs` for call information)"
+        )?;
+        return Ok(());
+    }
+
+    // Show callees with their own and worst case stack contributions
+    let graph = CallGraph::new(report);
+    let table_data: Vec<_> = f
+        .calls
+        .iter()
+        .map(|c| match graph.get(c) {
+            Some(callee) => {
+                let worst = graph
+                    .worst_path(c)
+                    .and_then(|(frames, _)| frames.last().map(|f| f.cumulative))
+                    .unwrap_or(callee.stack);
+                vec![
+                    callee.stack.cell(),
+                    worst.cell(),
+                    callee.display_name(transform).cell(),
+                ]
+            }
+            None => vec!["-".cell(), "-".cell(), transform.transform(c).cell()],
+        })
+        .collect();
+
+    writeln!(out, "Calls:")?;
+    out.table(table_data, vec!["STACK", "WORST", "NAME"])?;
+
+    if let Some((frames, recursive)) = graph.worst_path(&f.name) {
+        let worst = frames.last().map(|f| f.cumulative).unwrap_or(f.stack);
+        match recursive {
+            true => writeln!(out, "Worst case stack: {} bytes (recursive)", worst)?,
+            false => writeln!(out, "Worst case stack: {} bytes", worst)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Render the call graph, highlighting the worst path from the root
+fn show_dot(ctx: &Context, out: &mut Output, report: &Report) -> anyhow::Result<()> {
+    let (args, transform) = (ctx.args, ctx.transform);
+
+    if report.functions.iter().all(|f| f.calls.is_empty()) {
+        warn!("No call information found, link with `--emit-relocs` to enable call graph analysis");
+    }
+
+    let graph = CallGraph::new(report);
+    let path = args
+        .reachable_from
+        .as_ref()
+        .and_then(|r| graph.worst_path(r))
+        .map(|(frames, _)| frames)
+        .unwrap_or_default();
+
+    write!(out, "{}", graph.dot(transform, &path))?;
+
+    Ok(())
+}
+
+/// Show the largest regressions against the baseline
+fn show_top_growth(
+    ctx: &Context,
+    out: &mut Output,
+    report: &Report,
+    diff: &Diff,
+    n: usize,
+) -> anyhow::Result<()> {
+    let (args, transform) = (ctx.args, ctx.transform);
+
+    let mut grown: Vec<_> = diff
+        .changes_for(&report.functions)
+        .into_iter()
+        .filter(|c| c.stack.value() > 0)
+        .collect();
+    grown.sort_by_key(|c| std::cmp::Reverse(c.stack.value()));
+
+    let table_data: Vec<_> = grown
+        .iter()
+        .take(n)
+        .map(|c| {
+            let stack = match (c.stack.old, c.stack.new) {
+                (None, Some(n)) => format!("{} (new)", n),
+                (o, n) => format!("{} → {}", o.unwrap_or(0), n.unwrap_or(0)),
+            };
+            let mut line = vec![
+                stack.cell(),
+                format!("{:+}", c.stack.value()).cell(),
+                transform.transform(&c.function.name).cell(),
+            ];
+            if args.map_source {
+                line.push(c.function.source.clone().cell());
+            }
+            line
+        })
+        .collect();
+
+    let mut titles = vec!["STACK", "DELTA", "NAME"];
+    if args.map_source {
+        titles.push("SOURCE");
+    }
+
+    out.table(table_data, titles)?;
+
+    if grown.len() > n {
+        info!("Truncated {} regressions", grown.len() - n);
+    }
+
+    Ok(())
+}
+
+/// Write machine readable output, using the same filtered set as the table
+fn write_report(ctx: &Context, out: &mut Output, defined: Vec<Function>) -> anyhow::Result<()> {
+    let (args, diff) = (ctx.args, ctx.diff);
+    let n = defined.len().min(args.lines);
+
+    let r = Report {
+        functions: defined[..n].to_vec(),
+        ..Default::default()
+    };
+    match (&args.format, diff) {
+        (Format::Csv, Some(d)) => write!(out, "{}", changes_to_csv(&d.changes_for(&r.functions))?)?,
+        (Format::Csv, None) => write!(out, "{}", r.to_csv(None)?)?,
+        (Format::Folded, _) => write!(out, "{}", r.folded())?,
+        _ => writeln!(out, "{}", serde_json::to_string_pretty(&r)?)?,
+    }
+
+    if defined.len() > args.lines {
+        info!("Truncated {} lines", defined.len() - args.lines);
+    }
+
+    Ok(())
+}
+
+/// Show the function table
+fn show_table(
+    ctx: &Context,
+    out: &mut Output,
+    report: Report,
+    cumulative: &HashMap<String, u64>,
+    regressions: &HashMap<String, (i64, i64)>,
+) -> anyhow::Result<()> {
+    let (args, transform, prev, diff) = (ctx.args, ctx.transform, ctx.prev, ctx.diff);
+    let show_crate = args
+        .columns
+        .as_ref()
+        .map(|c| c.contains(&Column::Crate))
+        .unwrap_or(false);
+
+    let report_duplicates: Vec<String> = report
+        .duplicates()
+        .into_keys()
+        .map(|s| s.to_string())
+        .collect();
+    let defined = report.functions;
+
+    // Build table for display
+    let n = defined.len().min(args.lines);
+
+    // Determine source column width, defaulting to the remaining terminal width
+    let source_width = match (args.source_width, terminal_size::terminal_size()) {
+        (Some(w), _) => Some(w),
+        (None, Some((terminal_size::Width(w), _))) => {
+            let name_width = defined[..n]
+                .iter()
+                .map(|f| f.display_name(transform).chars().count())
+                .max()
+                .unwrap_or(0);
+            // Approximate width of ADDR, SIZE, STACK and padding
+            Some((w as usize).saturating_sub(name_width + 40).max(20))
+        }
+        _ => None,
+    };
+
+    // Setup highlight thresholds
+    let thresholds = Thresholds {
+        warn: args.warn,
+        crit: args.crit,
+    };
+
+    // Load symbol map for symbol-relative addresses if enabled
+    let symbols = match args.sym_addr {
+        true => {
+            let mut s = SymbolLookup::new();
+            for d in ctx.inputs {
+                s.add(d)?;
+            }
+            Some(s)
+        }
+        false => None,
+    };
+
+    // Flag duplicate functions where kept distinct
+    let duplicates: std::collections::HashSet<&str> = match args.keep_duplicates {
+        true => report_duplicates.iter().map(|s| s.as_str()).collect(),
+        false => Default::default(),
+    };
+
+    // Use separate old / new / delta columns if enabled
+    let diff_columns = prev.is_some() && args.diff_columns;
+
+    // Pair listed functions with their changes against the baseline
+    let changes = diff.map(|d| d.changes_for(&defined[..n]));
+    let rows: Vec<(&Function, Option<(StackDelta, StackDelta)>)> = match &changes {
+        Some(c) => c
+            .iter()
+            .map(|c| (&c.function, Some((c.text, c.stack))))
+            .collect(),
+        None => defined[..n].iter().map(|f| (f, None)).collect(),
+    };
+
+    let table_data: Vec<_> = rows
+        .iter()
+        .map(|(f, deltas)| {
+            let kind = deltas.map(|(_, stack)| stack.kind());
+
+            // Truncate name, marking functions added or removed since the baseline
+            let mut name = f.display_name(transform);
+            match kind {
+                Some(DeltaKind::Removed) => name = format!("{} (removed)", name),
+                Some(DeltaKind::Added) => name = format!("{} (new)", name),
+                _ => (),
+            }
+            if duplicates.contains(f.name.as_str()) {
+                name = format!("{} (duplicate)", name);
+            }
+
+            // Mark names over thresholds if enabled
+            if args.mark {
+                let m = thresholds.classify(f.stack).marker();
+                if !m.is_empty() {
+                    name = format!("{} {}", m, name);
+                }
+            }
+
+            // Setup display line
+            let addr = match symbols.as_ref().and_then(|s| s.symbol_at(f.addr)) {
+                Some((sym, offset)) if offset > 0 => {
+                    format!(
+                        "{}+0x{:x}",
+                        transform.transform(&format!("{:#}", demangle(sym))),
+                        offset
+                    )
+                }
+                _ => format!("0x{:08x}", f.addr),
+            };
+            let mut line = vec![addr.cell()];
+
+            let size = |v: u64| format_size(v, args.human);
+            let delta = |d: i64| format_delta(d, args.human);
+
+            // Colour stack by severity, and changes by direction
+            let sev = |v: u64| severity_color(thresholds.classify(v));
+
+            // Render changes from the diff, removed functions have no current size
+            match deltas {
+                Some((text, stack)) => {
+                    for (d, is_stack) in [(text, false), (stack, true)] {
+                        let (new, value) = (d.new.unwrap_or(0), d.value());
+                        let sev = |v| match is_stack {
+                            true => sev(v),
+                            false => None,
+                        };
+                        let color = delta_color(value).or(sev(new));
+                        let missing = || "-".to_string();
+
+                        match (diff_columns, args.diff_both) {
+                            (true, _) => line.extend([
+                                d.old.map(size).unwrap_or_else(missing).cell(),
+                                d.new
+                                    .map(size)
+                                    .unwrap_or_else(missing)
+                                    .cell()
+                                    .foreground_color(sev(new)),
+                                delta(value).cell().foreground_color(delta_color(value)),
+                            ]),
+                            (false, true) => line.push(
+                                format_both(d.old, new, args.human)
+                                    .cell()
+                                    .foreground_color(color),
+                            ),
+                            (false, false) => line.push(
+                                format!("{:<4} ({})", size(new), delta(value))
+                                    .cell()
+                                    .foreground_color(color),
+                            ),
+                        }
+                    }
+                }
+                None => {
+                    line.push(size(f.text).cell());
+                    line.push(size(f.stack).cell().foreground_color(sev(f.stack)));
+                }
+            }
+
+            // Show cumulative stack in place of stack (or alongside diffs) if enabled
+            if args.cumulative {
+                let c = cumulative.get(&f.name).copied().unwrap_or(f.stack);
+                if prev.is_none() {
+                    line.pop();
+                    line.push(size(c).cell().foreground_color(sev(c)));
+                }
+                line.push(match prev.is_none() {
+                    true => size(f.stack).cell(),
+                    false => size(c).cell().foreground_color(sev(c)),
+                });
+            }
+
+            // Add stack region percentage if enabled, flagging frames exceeding the region
+            if let Some(region) = args.stack_region {
+                let pct = f.stack as f64 * 100.0 / region.max(1) as f64;
+                let cell = match pct {
+                    p if p > 100.0 => format!("{:.0}% !", p)
+                        .cell()
+                        .foreground_color(Some(Color::Red)),
+                    p if p > args.region_warn as f64 => format!("{:.0}%", p)
+                        .cell()
+                        .foreground_color(Some(Color::Yellow)),
+                    p => format!("{:.0}%", p).cell(),
+                };
+                line.push(cell);
+            }
+
+            // Add ratio if enabled
+            if args.show_ratio {
+                line.push(format!("{:.2}", f.ratio()).cell());
+            }
+
+            // Add section if enabled
+            if args.show_section {
+                line.push(f.section.clone().cell());
+            }
+
+            // Add crate if selected
+            if show_crate {
+                line.push(crate_name(&f.name).unwrap_or(UNKNOWN_CRATE).cell());
+            }
+
+            line.push(name.cell());
+
+            // Add source location if enabled
+            if args.map_source {
+                let source = match source_width {
+                    _ if args.inline && !f.inline.is_empty() => f.inline.join(" <- "),
+                    Some(w) => truncate_middle(&f.source, w),
+                    None => f.source.clone(),
+                };
+                line.push(source.cell());
+            }
+
+            line
+        })
+        .collect();
+
+    let mut titles = match diff_columns {
+        true => vec![
+            "ADDR",
+            "SIZE OLD",
+            "SIZE NEW",
+            "SIZE DELTA",
+            "STACK OLD",
+            "STACK NEW",
+            "STACK DELTA",
+        ],
+        false => vec!["ADDR", "SIZE", "STACK"],
+    };
+    if args.cumulative {
+        match prev.is_none() {
+            true => {
+                titles.pop();
+                titles.extend(["CUMULATIVE", "FRAME"]);
+            }
+            false => titles.push("CUMULATIVE"),
+        }
+    }
+    if args.stack_region.is_some() {
+        titles.push("REGION");
+    }
+    if args.show_ratio {
+        titles.push("RATIO");
+    }
+    if args.show_section {
+        titles.push("SECTION");
+    }
+    if show_crate {
+        titles.push("CRATE");
+    }
+    titles.push("NAME");
+    if args.map_source {
+        titles.push("SOURCE");
+    }
+
+    // Show only selected columns in the order given if enabled, diff columns stay grouped
+    let mut table_data = table_data;
+    if let Some(columns) = &args.columns {
+        let order: Vec<_> = columns
+            .iter()
+            .flat_map(|c| {
+                let titles = &titles;
+                (0..titles.len()).filter(move |i| Column::from_title(titles[*i]) == Some(*c))
+            })
+            .collect();
+
+        table_data = table_data
+            .into_iter()
+            .map(|r| select_columns(r, &order))
+            .collect();
+        titles = select_columns(titles, &order);
+    }
+
+    // Add totals row for the listed functions if enabled
+    if args.summary {
+        let s = Report {
+            functions: defined[..n].to_vec(),
+            ..Default::default()
+        }
+        .summary();
+
+        // Totals by column title, other columns are left empty
+        let size = |v: u64| format_size(v, args.human);
+        let totals = [
+            ("SIZE", size(s.total_text)),
+            ("STACK", size(s.total_stack)),
+            ("FRAME", size(s.total_stack)),
+            (
+                "NAME",
+                format!(
+                    "{} functions, max stack {}",
+                    s.function_count,
+                    size(s.max_stack)
+                ),
+            ),
+        ];
+        let mut row: Vec<_> = titles
+            .iter()
+            .map(|t| {
+                totals
+                    .iter()
+                    .find(|(label, _)| label == t)
+                    .map(|(_, v)| v.clone())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        // Label the row in the first column, prefixing the total where this holds one
+        if let Some(first) = row.first_mut() {
+            *first = match first.is_empty() {
+                true => "TOTAL".to_string(),
+                false => format!("TOTAL: {}", first),
+            };
+        }
+        table_data.push(row.into_iter().map(|c| c.cell()).collect());
+    }
+
+    // Name the baseline for diffs
+    if let Some(b) = args.baseline() {
+        writeln!(out, "Comparing against baseline: {}", b)?;
+    }
+
+    out.table(table_data, titles)?;
+
+    // List functions over the frame limit if enabled
+    if let Some(limit) = args.frame_warn {
+        let mut over: Vec<_> = defined.iter().filter(|f| f.stack > limit).collect();
+        over.sort_by_key(|f| std::cmp::Reverse(f.stack));
+
+        if !over.is_empty() {
+            writeln!(out, "⚠ functions over {} bytes:", limit)?;
+            for f in over {
+                match f.source.is_empty() {
+                    true => writeln!(out, "  {:<6} {}", f.stack, f.name)?,
+                    false => writeln!(out, "  {:<6} {} ({})", f.stack, f.name, f.source)?,
+                }
+            }
+        }
+    }
+
+    // Show total regression over displayed functions if enabled
+    if args.regressions_only {
+        let shown: Vec<_> = defined
+            .iter()
+            .filter_map(|f| regressions.get(&f.name))
+            .collect();
+
+        // Text-only regressions have unchanged stack so are counted separately
+        let stack: Vec<_> = shown.iter().map(|r| r.0).filter(|d| *d > 0).collect();
+        writeln!(
+            out,
+            "Total stack regression: {} bytes across {} functions.",
+            format_delta(stack.iter().sum(), args.human),
+            stack.len()
+        )?;
+
+        if args.regressions_text {
+            let text: Vec<_> = shown.iter().map(|r| r.1).filter(|d| *d > 0).collect();
+            writeln!(
+                out,
+                "Total text regression: {} bytes across {} functions.",
+                format_delta(text.iter().sum(), args.human),
+                text.len()
+            )?;
+        }
+    }
+
+    // Show stack distribution over all filtered functions if enabled
+    if args.stats {
+        let s = Report {
+            functions: defined.clone(),
+            ..Default::default()
+        }
+        .stats();
+        writeln!(
+            out,
+            "Stack: mean {:.1}, median {}, p95 {}, max {} ({} functions)",
+            s.mean,
+            format_size(s.median, args.human),
+            format_size(s.p95, args.human),
+            format_size(s.max, args.human),
+            defined.len()
+        )?;
+    }
+
+    // List undefined functions if enabled
+    if args.show_undefined && !report.undefined.is_empty() {
+        writeln!(out, "Undefined functions ({}):", report.undefined.len())?;
+        for u in &report.undefined {
+            writeln!(out, "  {}", transform.transform(u))?;
+        }
+    }
 
     // Warn on truncation
     if defined.len() > args.lines {
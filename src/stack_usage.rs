@@ -0,0 +1,95 @@
+use log::{debug, warn};
+use regex::Regex;
+
+use crate::{Abi, Function};
+
+lazy_static::lazy_static! {
+    /// `file:line[:col]:name` location prefix, the column is omitted by older GCC versions
+    static ref LOCATION: Regex = Regex::new(r"^(.*?):(\d+)(?::\d+)?:(.+)$").unwrap();
+}
+
+/// Stack usage qualifier from `-fstack-usage` output
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StackQualifier {
+    /// Fixed stack size
+    Static,
+    /// Dynamic stack allocation, size is a lower bound
+    Dynamic,
+    /// Dynamic stack allocation bounded by the reported size
+    Bounded,
+}
+
+impl StackQualifier {
+    /// Parse a (comma-separated) qualifier, e.g. `dynamic,bounded`
+    pub fn parse(s: &str) -> Result<Self, anyhow::Error> {
+        let mut dynamic = false;
+        let mut bounded = false;
+
+        for q in s.split(',').map(|q| q.trim()) {
+            match q {
+                "static" => (),
+                "dynamic" => dynamic = true,
+                "bounded" => bounded = true,
+                _ => return Err(anyhow::anyhow!("unknown stack usage qualifier '{}'", q)),
+            }
+        }
+
+        Ok(match (dynamic, bounded) {
+            (_, true) => StackQualifier::Bounded,
+            (true, false) => StackQualifier::Dynamic,
+            (false, false) => StackQualifier::Static,
+        })
+    }
+}
+
+/// Parse a single `.su` line (`file:line:col:name\tsize\tqualifier`)
+///
+/// Returns the function, with source location and stack size, and the stack qualifier.
+pub fn parse_su_line(line: &str) -> Result<(Function, StackQualifier), anyhow::Error> {
+    let mut fields = line.split('\t');
+    let (location, size, qualifier) = match (fields.next(), fields.next(), fields.next()) {
+        (Some(l), Some(s), Some(q)) => (l, s, q),
+        _ => return Err(anyhow::anyhow!("expected three tab-separated fields")),
+    };
+
+    let c = LOCATION
+        .captures(location)
+        .ok_or_else(|| anyhow::anyhow!("invalid location '{}'", location))?;
+
+    let name = c[3].to_string();
+    let function = Function {
+        abi: Abi::from_symbol(&name),
+        name,
+        stack: size.trim().parse()?,
+        source: format!("{}:{}", &c[1], &c[2]),
+        ..Default::default()
+    };
+
+    Ok((function, StackQualifier::parse(qualifier)?))
+}
+
+/// Parse functions from `.su` stack usage data (as emitted by `-fstack-usage`)
+pub(crate) fn parse_su(s: &str) -> Result<Vec<Function>, anyhow::Error> {
+    let mut functions = vec![];
+    let mut unbounded = 0;
+
+    for (i, line) in s.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let (f, q) = parse_su_line(line).map_err(|e| anyhow::anyhow!("line {}: {}", i + 1, e))?;
+
+        if q == StackQualifier::Dynamic {
+            debug!("Unbounded dynamic stack use: {}", f.name);
+            unbounded += 1;
+        }
+
+        functions.push(f);
+    }
+
+    if unbounded > 0 {
+        warn!(
+            "{} functions use unbounded dynamic stack, sizes are a lower bound",
+            unbounded
+        );
+    }
+
+    Ok(functions)
+}
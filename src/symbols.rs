@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+
+use addr2line::object::{
+    Architecture, Object, ObjectSection, ObjectSymbol, SymbolKind, SymbolMap, SymbolMapName,
+};
+use log::{debug, info};
+use stack_sizes::{analyze_executable, analyze_object};
+
+/// Function symbol with stack information, prior to report processing
+pub(crate) struct Symbol<'a> {
+    /// Symbol address (section-relative for object files)
+    pub addr: u64,
+
+    /// Mangled name and aliases
+    pub names: Vec<&'a str>,
+
+    /// Text size
+    pub size: u64,
+
+    /// Stack size, if available
+    pub stack: Option<u64>,
+
+    /// Containing section, where known from the symbol table
+    pub section: Option<String>,
+}
+
+/// Symbols loaded from an ELF or object file
+pub(crate) struct Symbols<'a> {
+    /// Defined function symbols
+    pub defined: Vec<Symbol<'a>>,
+
+    /// Undefined (external) symbol names
+    pub undefined: Vec<&'a str>,
+}
+
+/// Collect (masked) addresses of Thumb function symbols, with the low address bit set
+pub(crate) fn thumb_symbols(d: &[u8]) -> Result<HashSet<u64>, anyhow::Error> {
+    let object = addr2line::object::File::parse(d)?;
+
+    Ok(object
+        .symbols()
+        .filter(|s| s.kind() == SymbolKind::Text && s.address() & 1 == 1)
+        .map(|s| s.address() & !1)
+        .collect())
+}
+
+/// Load function symbols from an executable via `stack_sizes`
+pub(crate) fn load_executable(d: &[u8], arch: Architecture) -> Result<Symbols<'_>, anyhow::Error> {
+    let parsed = analyze_executable(d)?;
+    let object = addr2line::object::File::parse(d)?;
+
+    // Thumb function symbols have the low address bit set
+    let mask = match arch {
+        Architecture::Arm => !1,
+        _ => !0,
+    };
+
+    // Symbol table sizes by address, for functions missing text sizes
+    let mut sizes: HashMap<u64, u64> = HashMap::new();
+    for s in object.symbols().filter(|s| s.kind() == SymbolKind::Text) {
+        let e = sizes.entry(s.address() & mask).or_default();
+        *e = (*e).max(s.size());
+    }
+
+    let mut fallbacks = 0;
+    let defined = parsed
+        .defined
+        .iter()
+        .map(|(addr, f)| {
+            // Fall back to the symbol table where stack-sizes reports no text
+            let size = match (f.size(), sizes.get(&(*addr & mask))) {
+                (0, Some(s)) if *s > 0 => {
+                    debug!("Using symbol table size for {}: {}", f.names()[0], s);
+                    fallbacks += 1;
+                    *s
+                }
+                (s, _) => s,
+            };
+
+            Symbol {
+                addr: *addr,
+                names: f.names().to_vec(),
+                size,
+                stack: f.stack(),
+                section: None,
+            }
+        })
+        .collect();
+
+    if fallbacks > 0 {
+        info!("Using symbol table sizes for {} functions", fallbacks);
+    }
+
+    Ok(Symbols {
+        defined,
+        undefined: parsed.undefined.into_iter().collect(),
+    })
+}
+
+/// Load function symbols from a relocatable object file
+///
+/// Stack sizes are provided by `stack_sizes` by name, with addresses and sizes
+/// resolved from the symbol table. Addresses are section-relative offsets as
+/// object files are not yet linked.
+pub(crate) fn load_object(d: &[u8], arch: Architecture) -> Result<Symbols<'_>, anyhow::Error> {
+    let sizes = analyze_object(d)?;
+    let object = addr2line::object::File::parse(d)?;
+
+    // Thumb function symbols have the low address bit set
+    let mask = match arch {
+        Architecture::Arm => !1,
+        _ => !0,
+    };
+
+    let mut defined = vec![];
+    let mut undefined = vec![];
+    let mut seen = HashSet::new();
+
+    for s in object.symbols() {
+        let name = match s.name() {
+            Ok(n) if !n.is_empty() => n,
+            _ => continue,
+        };
+
+        if s.is_undefined() {
+            undefined.push(name);
+            continue;
+        }
+
+        if s.kind() != SymbolKind::Text {
+            continue;
+        }
+
+        let section = s
+            .section_index()
+            .and_then(|i| object.section_by_index(i).ok())
+            .and_then(|s| s.name().ok().map(|n| n.to_string()));
+
+        seen.insert(name);
+        defined.push(Symbol {
+            addr: s.address() & mask,
+            names: vec![name],
+            size: s.size(),
+            stack: sizes.get(name).copied(),
+            section,
+        });
+    }
+
+    // Functions with stack information but no symbol table entry are keyed by name
+    for (name, stack) in sizes.iter().filter(|(n, _)| !seen.contains(*n)) {
+        defined.push(Symbol {
+            addr: 0,
+            names: vec![name],
+            size: 0,
+            stack: Some(*stack),
+            section: None,
+        });
+    }
+
+    Ok(Symbols { defined, undefined })
+}
+
+/// Load function symbols from any supported object format (e.g. Mach-O, PE)
+///
+/// Stack sizes are not available from the symbol table and must be provided
+/// separately. Where symbols have no size, sizes are estimated from the
+/// distance to the next symbol in the same section.
+pub(crate) fn load_symbols(d: &[u8], arch: Architecture) -> Result<Symbols<'_>, anyhow::Error> {
+    let object = addr2line::object::File::parse(d)?;
+
+    // Thumb function symbols have the low address bit set
+    let mask = match arch {
+        Architecture::Arm => !1,
+        _ => !0,
+    };
+
+    // Collect function symbols by section, ordered by address
+    let mut by_section: HashMap<_, Vec<_>> = HashMap::new();
+    let mut undefined = vec![];
+    for s in object.symbols() {
+        let name = match s.name() {
+            Ok(n) if !n.is_empty() => n,
+            _ => continue,
+        };
+
+        if s.is_undefined() {
+            undefined.push(name);
+            continue;
+        }
+
+        if let (SymbolKind::Text, Some(idx)) = (s.kind(), s.section_index()) {
+            by_section
+                .entry(idx)
+                .or_default()
+                .push((s.address() & mask, s.size(), name));
+        }
+    }
+
+    let mut defined = vec![];
+    for (idx, mut symbols) in by_section {
+        symbols.sort();
+
+        let section = object.section_by_index(idx).ok();
+        let end = section.as_ref().map(|s| s.address() + s.size());
+        let section = section.and_then(|s| s.name().ok().map(|n| n.to_string()));
+
+        for (i, (addr, size, name)) in symbols.iter().enumerate() {
+            // Estimate missing sizes from the next symbol or section end
+            let next = symbols[i + 1..]
+                .iter()
+                .map(|s| s.0)
+                .find(|a| a > addr)
+                .or(end);
+            let size = match (*size, next) {
+                (0, Some(n)) => n.saturating_sub(*addr),
+                (s, _) => s,
+            };
+
+            defined.push(Symbol {
+                addr: *addr,
+                names: vec![*name],
+                size,
+                stack: None,
+                section: section.clone(),
+            });
+        }
+    }
+    defined.sort_by_key(|s| s.addr);
+
+    debug!("Loaded {} function symbols", defined.len());
+
+    Ok(Symbols { defined, undefined })
+}
+
+/// Address to symbol lookup over one or more ELF or object files
+///
+/// Used for rendering addresses as `symbol+offset`, this reads only symbol
+/// tables so does not require debug information.
+#[derive(Default)]
+pub struct SymbolLookup<'a> {
+    maps: Vec<(SymbolMap<SymbolMapName<'a>>, HashMap<u64, u64>)>,
+}
+
+impl<'a> SymbolLookup<'a> {
+    /// Create an empty lookup
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add symbols from an ELF or object file, earlier files take precedence
+    pub fn add(&mut self, d: &'a [u8]) -> Result<(), anyhow::Error> {
+        let object = addr2line::object::File::parse(d)?;
+
+        // Thumb function symbols have the low address bit set
+        let mask = match object.architecture() {
+            Architecture::Arm => !1,
+            _ => !0,
+        };
+
+        let mut names = vec![];
+        let mut sizes: HashMap<u64, u64> = HashMap::new();
+        for s in object.symbols() {
+            let name = match s.name() {
+                Ok(n) if !n.is_empty() => n,
+                _ => continue,
+            };
+
+            // Include untyped symbols, e.g. local labels and thunks
+            let code = matches!(s.kind(), SymbolKind::Text | SymbolKind::Unknown);
+            if !code || s.is_undefined() || s.section_index().is_none() {
+                continue;
+            }
+
+            let addr = s.address() & mask;
+            names.push(SymbolMapName::new(addr, name));
+            let e = sizes.entry(addr).or_default();
+            *e = (*e).max(s.size());
+        }
+
+        self.maps.push((SymbolMap::new(names), sizes));
+        Ok(())
+    }
+
+    /// Find the symbol containing an address, returning the symbol name and offset
+    ///
+    /// Addresses past the end of a symbol are not matched where the symbol size is known.
+    pub fn symbol_at(&self, addr: u64) -> Option<(&'a str, u64)> {
+        self.maps.iter().find_map(|(symbols, sizes)| {
+            let s = symbols.get(addr)?;
+            let offset = addr - s.address();
+            match sizes.get(&s.address()) {
+                Some(size) if *size > 0 && offset >= *size => None,
+                _ => Some((s.name(), offset)),
+            }
+        })
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{CallGraph, Report};
+
+/// Task (thread) with assigned functions and an optional stack budget
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    /// Task name
+    pub name: String,
+
+    /// Function name prefixes assigned to the task
+    pub prefixes: Vec<String>,
+
+    /// Task stack budget
+    pub limit: Option<u64>,
+}
+
+/// Worst case stack summary for a task
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskSummary {
+    /// Task name
+    pub name: String,
+
+    /// Number of functions assigned to the task
+    pub functions: usize,
+
+    /// Worst case stack among assigned functions, including callees where known
+    pub worst: u64,
+
+    /// Function with the worst case stack
+    pub worst_function: Option<String>,
+
+    /// Task stack budget
+    pub limit: Option<u64>,
+}
+
+impl TaskSummary {
+    /// Check whether the task exceeds its budget
+    pub fn over_limit(&self) -> bool {
+        self.limit.map(|l| self.worst > l).unwrap_or(false)
+    }
+}
+
+/// Task assignments, loaded from `name[:limit] = prefix, prefix` lines
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tasks {
+    pub tasks: Vec<Task>,
+}
+
+impl Tasks {
+    /// Load task assignments from a file
+    pub fn load(file: &str) -> Result<Self, anyhow::Error> {
+        let s = std::fs::read_to_string(file)?;
+        Self::parse(&s)
+    }
+
+    /// Parse task assignments, `#` starts a comment
+    pub fn parse(s: &str) -> Result<Self, anyhow::Error> {
+        let mut tasks = vec![];
+
+        for (i, l) in s.lines().enumerate() {
+            let l = l.trim();
+            if l.is_empty() || l.starts_with('#') {
+                continue;
+            }
+
+            let (task, prefixes) = l.split_once('=').ok_or_else(|| {
+                anyhow::anyhow!("line {}: expected `name[:limit] = prefix, ...`", i + 1)
+            })?;
+
+            // Parse optional task limit
+            let (name, limit) = match task.split_once(':') {
+                Some((n, l)) => {
+                    let l = l.trim().parse().map_err(|e| {
+                        anyhow::anyhow!("line {}: invalid task limit: {}", i + 1, e)
+                    })?;
+                    (n, Some(l))
+                }
+                None => (task, None),
+            };
+
+            let prefixes = prefixes
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect();
+
+            tasks.push(Task {
+                name: name.trim().to_string(),
+                prefixes,
+                limit,
+            });
+        }
+
+        Ok(Self { tasks })
+    }
+
+    /// Compute per-task worst case stack use
+    ///
+    /// Where call information is available this is the worst call chain depth
+    /// from each assigned function, otherwise the largest individual frame.
+    pub fn summarise(&self, report: &Report) -> Vec<TaskSummary> {
+        let (depths, _) = CallGraph::new(report).max_depths();
+
+        self.tasks
+            .iter()
+            .map(|t| {
+                let assigned: Vec<_> = report
+                    .functions
+                    .iter()
+                    .filter(|f| t.prefixes.iter().any(|p| f.name.starts_with(p)))
+                    .collect();
+
+                let worst = assigned
+                    .iter()
+                    .map(|f| {
+                        let depth = depths.get(f.name.as_str()).copied().unwrap_or(f.stack);
+                        (depth, f.name.clone())
+                    })
+                    .max();
+
+                TaskSummary {
+                    name: t.name.clone(),
+                    functions: assigned.len(),
+                    worst: worst.as_ref().map(|(d, _)| *d).unwrap_or(0),
+                    worst_function: worst.map(|(_, n)| n),
+                    limit: t.limit,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,59 @@
+//! Tests for call graph analysis
+
+mod common;
+
+use common::{call_report as report, names};
+use whatthestack::*;
+
+/// Call graph with a cycle (`a::loop` <-> `a::back`) and an unreachable function
+fn graph() -> Report {
+    report(&[
+        ("main", 16, &["a::loop", "a::leaf", "memcpy"]),
+        ("a::loop", 32, &["a::back"]),
+        ("a::back", 8, &["a::loop"]),
+        ("a::leaf", 64, &[]),
+        ("a::unused", 128, &["a::leaf"]),
+    ])
+}
+
+#[test]
+fn prune_unreachable() {
+    let mut r = graph();
+
+    // Cycles terminate and undefined callees are ignored
+    assert_eq!(r.prune_unreachable("main").unwrap(), 1);
+    assert_eq!(names(&r), ["main", "a::loop", "a::back", "a::leaf"]);
+
+    // Pruning from a leaf keeps only the leaf
+    assert_eq!(r.prune_unreachable("a::leaf").unwrap(), 3);
+    assert_eq!(r.functions.len(), 1);
+
+    let e = r.prune_unreachable("missing").unwrap_err();
+    assert!(e.to_string().contains("'missing' not found"));
+    assert_eq!(r.functions.len(), 1);
+}
+
+#[test]
+fn dot_output() {
+    let r = report(&[
+        ("main", 16, &["a::leaf", "a::\"quoted\"", "memcpy"]),
+        ("a::leaf", 64, &[]),
+        ("a::\"quoted\"", 8, &[]),
+    ]);
+    let graph = CallGraph::new(&r);
+    let (path, _) = graph.worst_path("main").unwrap();
+
+    // Nodes are ordered by name, undefined callees are omitted and the worst path highlighted
+    assert_eq!(
+        graph.dot(&LongNames, &path),
+        r#"digraph calls {
+    node [shape=box];
+    "a::\"quoted\"" [label="a::\"quoted\"\n8 bytes"];
+    "a::leaf" [label="a::leaf\n64 bytes"];
+    "main" [label="main\n16 bytes"];
+    "main" -> "a::leaf" [color=red, penwidth=2, label="80"];
+    "main" -> "a::\"quoted\"";
+}
+"#
+    );
+}
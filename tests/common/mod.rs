@@ -0,0 +1,67 @@
+//! Helpers shared between integration tests
+//!
+//! Each test crate uses a subset of these
+#![allow(dead_code)]
+
+use whatthestack::*;
+
+/// Resolve a fixture path, see `fixtures/build.sh`
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Run `wts --quiet` with the provided arguments
+pub fn wts(args: &[&str]) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_wts"))
+        .arg("--quiet")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Build a report from `(name, text, stack)` entries
+pub fn report(functions: &[(&str, u64, u64)]) -> Report {
+    Report {
+        functions: functions
+            .iter()
+            .map(|(name, text, stack)| Function {
+                name: name.to_string(),
+                text: *text,
+                stack: *stack,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Build a report from `(name, stack, calls)` entries
+pub fn call_report(functions: &[(&str, u64, &[&str])]) -> Report {
+    Report {
+        functions: functions
+            .iter()
+            .map(|(name, stack, calls)| Function {
+                name: name.to_string(),
+                stack: *stack,
+                calls: calls.iter().map(|c| c.to_string()).collect(),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Report with mixed text and stack sizes across two crates
+pub fn sized() -> Report {
+    report(&[
+        ("a::tiny", 8, 0),
+        ("a::small", 32, 16),
+        ("a::medium", 64, 64),
+        ("b::large", 512, 256),
+    ])
+}
+
+/// Function names in report order
+pub fn names(r: &Report) -> Vec<&str> {
+    r.functions.iter().map(|f| f.name.as_str()).collect()
+}
@@ -0,0 +1,41 @@
+//! Tests for per-crate aggregation
+
+mod common;
+
+use common::sized;
+use whatthestack::*;
+
+#[test]
+fn group_by_crate() {
+    let mut r = sized();
+    r.functions.push(Function {
+        name: "_start".to_string(),
+        text: 4,
+        stack: 8,
+        ..Default::default()
+    });
+
+    assert_eq!(
+        r.group_by_crate(),
+        vec![
+            ("b".to_string(), 512, 256),
+            ("a".to_string(), 104, 80),
+            (UNKNOWN_CRATE.to_string(), 4, 8),
+        ]
+    );
+}
+
+#[test]
+fn crates_sorted_order() {
+    let r = sized();
+    let names = |sort, order| -> Vec<_> {
+        r.crates_sorted(&sort, order)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    };
+
+    assert_eq!(names(Sort::Stack, Order::Desc), vec!["b", "a"]);
+    assert_eq!(names(Sort::Stack, Order::Asc), vec!["a", "b"]);
+    assert_eq!(names(Sort::Name, Order::Desc), vec!["b", "a"]);
+}
@@ -0,0 +1,262 @@
+//! Tests for report comparison
+
+mod common;
+
+use common::{fixture, report, wts};
+use std::io::Write;
+use whatthestack::*;
+
+#[test]
+fn compare() {
+    let prev = report(&[
+        ("a::same", 16, 8),
+        ("a::grown", 32, 16),
+        ("a::text", 32, 16),
+        ("a::gone", 8, 8),
+    ]);
+    let current = report(&[
+        ("a::same", 16, 8),
+        ("a::grown", 32, 48),
+        ("a::text", 64, 16),
+        ("a::new", 4, 24),
+    ]);
+
+    let d = current.compare(&prev);
+    let names = |c: &[FunctionChange]| -> Vec<String> {
+        c.iter().map(|c| c.function.name.clone()).collect()
+    };
+
+    // Unchanged functions are omitted
+    assert_eq!(names(&d.added), ["a::new"]);
+    assert_eq!(names(&d.removed), ["a::gone"]);
+    assert_eq!(names(&d.changed), ["a::grown", "a::text"]);
+
+    assert_eq!(d.added[0].stack.kind(), DeltaKind::Added);
+    assert_eq!(d.added[0].stack.value(), 24);
+    assert_eq!(d.removed[0].stack.kind(), DeltaKind::Removed);
+    assert_eq!(d.removed[0].stack.value(), -8);
+    assert_eq!(d.changed[0].stack.delta(), Some(32));
+    assert_eq!(d.changed[1].stack.kind(), DeltaKind::Unchanged);
+    assert_eq!(d.changed[1].text.delta(), Some(32));
+
+    assert!(prev.compare(&prev).is_empty());
+}
+
+#[test]
+fn regressions() {
+    let prev = report(&[
+        ("a::grown", 32, 16),
+        ("a::text", 32, 16),
+        ("a::gone", 8, 64),
+        ("a::traded", 32, 32),
+    ]);
+    let current = report(&[
+        ("a::grown", 32, 48),
+        ("a::text", 64, 16),
+        ("a::new", 4, 24),
+        ("a::traded", 64, 16),
+    ]);
+
+    let d = current.compare(&prev);
+    let names = |include_text| -> Vec<String> {
+        d.regressions(include_text)
+            .iter()
+            .map(|c| c.function.name.clone())
+            .collect()
+    };
+
+    // Removed functions and text growth with a shrunk stack never count as regressions
+    assert_eq!(names(false), ["a::new", "a::grown"]);
+    assert_eq!(names(true), ["a::new", "a::grown", "a::text"]);
+}
+
+#[test]
+fn compare_renamed() {
+    let mut prev = report(&[
+        ("old::a", 16, 8),
+        ("old::b::c", 32, 16),
+        ("other::old::d", 8, 8),
+    ]);
+    let current = report(&[
+        ("new::a", 16, 8),
+        ("new::b::c", 32, 24),
+        ("other::old::d", 8, 8),
+    ]);
+
+    // Without renames each function shows as removed and re-added
+    let d = current.compare(&prev);
+    assert_eq!((d.added.len(), d.removed.len()), (2, 2));
+
+    // Renames match prefixes only, leaving changes in renamed functions
+    prev.rename_prefix("old::", "new::");
+    let d = current.compare(&prev);
+    assert!(d.added.is_empty() && d.removed.is_empty());
+    assert_eq!(d.changed.len(), 1);
+    assert_eq!(d.changed[0].function.name, "new::b::c");
+    assert_eq!(d.changed[0].stack.delta(), Some(8));
+}
+
+#[test]
+fn fingerprint() {
+    let r = report(&[("a::one", 16, 8), ("a::two", 32, 16)]);
+    let mut reordered = report(&[("a::two", 32, 16), ("a::one", 16, 8)]);
+
+    // Stable across ordering, addresses and source locations
+    reordered.functions[0].addr = 0x1000;
+    reordered.functions[1].source = "src/a.rs:1".to_string();
+    assert_eq!(r.fingerprint(), reordered.fingerprint());
+
+    // Sensitive to stack, text and name changes
+    let fp = |functions| report(functions).fingerprint();
+    assert_ne!(
+        r.fingerprint(),
+        fp(&[("a::one", 16, 12), ("a::two", 32, 16)])
+    );
+    assert_ne!(
+        r.fingerprint(),
+        fp(&[("a::one", 20, 8), ("a::two", 32, 16)])
+    );
+    assert_ne!(
+        r.fingerprint(),
+        fp(&[("a::one", 16, 8), ("a::three", 32, 16)])
+    );
+
+    // Values are not interchangeable between fields
+    assert_ne!(fp(&[("a", 8, 16)]), fp(&[("a", 16, 8)]));
+}
+
+/// Run `wts --compare current baseline` with additional arguments
+fn compare_files(current: &str, baseline: &str, args: &[&str]) -> std::process::Output {
+    let (current, baseline) = (fixture(current), fixture(baseline));
+    wts(&[args, &["--compare", &current, &baseline]].concat())
+}
+
+/// Stack delta for `big` from `wts --compare current baseline`
+fn compare_big(current: &str, baseline: &str) -> i64 {
+    let out = compare_files(current, baseline, &["--format", "csv"]);
+    assert!(out.status.success());
+
+    let csv = String::from_utf8(out.stdout).unwrap();
+    let row = csv.lines().find(|l| l.contains(",big,")).unwrap();
+    row.split(',').nth(6).unwrap().parse().unwrap()
+}
+
+#[test]
+fn compare_signs() {
+    // `big` uses 296 bytes on thumb and 152 bytes on x86_64
+    assert_eq!(compare_big("thumb.elf", "basic.elf"), 144);
+    assert_eq!(compare_big("basic.elf", "thumb.elf"), -144);
+}
+
+#[test]
+fn top_growth_filtered() {
+    // Growth is shown for filtered functions only
+    let out = compare_files(
+        "thumb.elf",
+        "basic.elf",
+        &["--top-growth", "5", "--filter", "sm"],
+    );
+    assert!(out.status.success());
+    let table = String::from_utf8(out.stdout).unwrap();
+    assert!(table.contains("small"));
+    assert!(!table.contains("big"));
+
+    // While the stack budget applies prior to filtering
+    let out = compare_files(
+        "thumb.elf",
+        "basic.elf",
+        &["--top-growth", "5", "--filter", "sm", "--max-stack", "100"],
+    );
+    assert!(!out.status.success());
+}
+
+#[test]
+fn compare_stdin() {
+    // The baseline is loaded like the current input, including from stdin
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_wts"))
+        .args(["--quiet", "--format", "csv", "--compare"])
+        .args([&fixture("thumb.elf"), "-"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let baseline = std::fs::read(fixture("basic.elf")).unwrap();
+    child.stdin.take().unwrap().write_all(&baseline).unwrap();
+
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    let csv = String::from_utf8(out.stdout).unwrap();
+    assert!(
+        csv.lines()
+            .any(|l| l.contains(",big,") && l.contains(",144,")),
+        "{}",
+        csv
+    );
+}
+
+#[test]
+fn fail_on_growth() {
+    // Output is shown before failing on growth, as with the stack budget
+    let out = compare_files("thumb.elf", "basic.elf", &["--fail-on-growth", "100"]);
+    assert!(!out.status.success());
+    let table = String::from_utf8(out.stdout).unwrap();
+    assert!(table.contains("big"), "{}", table);
+
+    let out = compare_files("thumb.elf", "basic.elf", &["--fail-on-growth", "200"]);
+    assert!(out.status.success());
+}
+
+#[test]
+fn compare_markers() {
+    // `many.elf` shares only `_start` with `basic.elf`, removed functions are only listed in
+    // diff modes
+    for (args, removed) in [
+        (&[][..], false),
+        (&["--changed-only"], true),
+        (&["--diff-columns"], true),
+        (&["--diff-both"], true),
+    ] {
+        let out = compare_files("basic.elf", "many.elf", &[args, &["-n", "1000"]].concat());
+        assert!(out.status.success());
+
+        // New and removed functions are marked the same way in every mode
+        let table = String::from_utf8(out.stdout).unwrap();
+        assert!(table.contains("big (new)"), "{:?}\n{}", args, table);
+        assert!(table.contains("+186"), "{:?}\n{}", args, table);
+        assert_eq!(
+            table.contains("many::frame (removed)"),
+            removed,
+            "{:?}\n{}",
+            args,
+            table
+        );
+    }
+}
+
+#[test]
+fn compare_removed_lines() {
+    // Removed functions are sorted and truncated with the listed functions
+    let out = compare_files("basic.elf", "many.elf", &["--changed-only", "-n", "2"]);
+    assert!(out.status.success());
+
+    let table = String::from_utf8(out.stdout).unwrap();
+    let rows: Vec<_> = table.lines().skip(2).collect();
+    assert_eq!(rows.len(), 2, "{}", table);
+    assert!(
+        rows.iter().all(|r| r.contains("many::frame (removed)")),
+        "{}",
+        table
+    );
+}
+
+#[test]
+fn diff_duplicates() {
+    // Duplicate names match the first entry in either report, as `Report::find`
+    let prev = report(&[("a::dup", 8, 16), ("a::dup", 8, 32)]);
+    let current = report(&[("a::dup", 8, 16), ("a::dup", 8, 64)]);
+
+    let d = current.diff(&prev);
+    assert_eq!(d.len(), 2);
+    let deltas: Vec<_> = d.iter().map(|d| d.stack_delta()).collect();
+    assert_eq!(deltas, [0, 48]);
+}
@@ -0,0 +1,39 @@
+//! Tests for `--explain` function details
+
+mod common;
+
+use common::{fixture, wts};
+
+/// Run `wts --explain` against a fixture, returning the output
+fn explain(name: &str, file: &str, args: &[&str]) -> String {
+    let out = wts(&[&["--explain", name], args, &[&fixture(file)]].concat());
+    assert!(out.status.success());
+
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn explain_inline() {
+    let out = explain("outer", "inline.elf", &["--map-source"]);
+    let lines: Vec<_> = out.lines().collect();
+
+    assert!(lines.contains(&"Name: outer"));
+
+    // Inlined frames are listed innermost first, ending at the function itself
+    let i = lines
+        .iter()
+        .position(|l| *l == "Inlined (innermost first):")
+        .unwrap();
+    assert!(lines[i + 1].ends_with("(core::hint::black_box::<usize>)"));
+    assert!(lines[i + 2].ends_with("inline.rs:10 (inline::scale)"));
+    assert!(lines[i + 3].ends_with("inline.rs:17 (outer)"));
+}
+
+#[test]
+fn explain_calls() {
+    let out = explain("small", "basic.elf", &[]);
+
+    assert!(out.contains("Stack: 8 bytes"));
+    assert!(!out.contains("Inlined"));
+    assert!(out.contains("Worst case stack: 160 bytes"));
+}
@@ -0,0 +1,52 @@
+//! Tests for sort expressions
+
+use whatthestack::*;
+
+/// Evaluate an expression over a function with `stack = 16`, `text = 100`, `addr = 0x1000`
+fn eval(s: &str) -> f64 {
+    let f = Function {
+        addr: 0x1000,
+        text: 100,
+        stack: 16,
+        ..Default::default()
+    };
+    s.parse::<SortExpr>().unwrap().eval(&f)
+}
+
+#[test]
+fn expr_valid() {
+    assert_eq!(eval("stack"), 16.0);
+    assert_eq!(eval("stack + text"), 116.0);
+    assert_eq!(eval("addr - 4096"), 0.0);
+    assert_eq!(eval("ratio"), 0.16);
+    assert_eq!(eval("text / 4 - 0.5"), 24.5);
+    assert_eq!(eval("  stack*2+text "), 132.0);
+}
+
+#[test]
+fn expr_precedence() {
+    // Products bind tighter than sums, operators are left associative
+    assert_eq!(eval("stack * 2 + text"), 132.0);
+    assert_eq!(eval("text + stack * 2"), 132.0);
+    assert_eq!(eval("(text + stack) * 2"), 232.0);
+    assert_eq!(eval("text - stack - 4"), 80.0);
+    assert_eq!(eval("text / 10 / 2"), 5.0);
+
+    // Negation applies to the following operand only
+    assert_eq!(eval("-stack + text"), 84.0);
+    assert_eq!(eval("-(stack + text)"), -116.0);
+}
+
+#[test]
+fn expr_errors() {
+    let err = |s: &str| s.parse::<SortExpr>().unwrap_err().to_string();
+
+    let e = err("stack + size");
+    assert!(e.contains("unknown identifier 'size'"), "{}", e);
+    assert!(e.contains("stack, text, addr, ratio"), "{}", e);
+
+    assert!(err("stack +").contains("unexpected end"));
+    assert!(err("(stack + text").contains("missing ')'"));
+    assert!(err("stack text").contains("unexpected 'text'"));
+    assert!(err("stack % 2").contains("invalid character '%'"));
+}
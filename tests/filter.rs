@@ -0,0 +1,198 @@
+//! Tests for report filtering
+
+mod common;
+
+use common::{fixture, names, sized, wts};
+use whatthestack::*;
+
+/// Apply filters, returning the remaining names in stack order
+fn filtered(filters: &FilterSet) -> Vec<String> {
+    let mut r = sized();
+    r.sort(Sort::Stack, Order::Desc);
+    r.retain(|f| filters.matches(f));
+    r.functions.into_iter().map(|f| f.name).collect()
+}
+
+#[test]
+fn min_stack() {
+    let f = FilterSet::new().min_size(SizeField::Stack, 64);
+    assert_eq!(filtered(&f), &["b::large", "a::medium"]);
+}
+
+#[test]
+fn min_text() {
+    let f = FilterSet::new().min_size(SizeField::Text, 32);
+    assert_eq!(filtered(&f), &["b::large", "a::medium", "a::small"]);
+}
+
+#[test]
+fn size_range() {
+    let f = FilterSet::new()
+        .min_size(SizeField::Stack, 16)
+        .max_size(SizeField::Stack, 64);
+    assert_eq!(filtered(&f), &["a::medium", "a::small"]);
+}
+
+#[test]
+fn prefix_and_size() {
+    let f = FilterSet::new().prefix("a::").min_size(SizeField::Stack, 1);
+    assert_eq!(filtered(&f), &["a::medium", "a::small"]);
+}
+
+#[test]
+fn prefixes_or() {
+    let f = FilterSet::new().prefix("a::s").prefix("b::");
+    assert_eq!(filtered(&f), &["b::large", "a::small"]);
+}
+
+#[test]
+fn prefix_and_regex() {
+    // Prefixes select candidates, the regex must also match
+    let f = FilterSet::new()
+        .prefix("a::")
+        .prefix("b::")
+        .regex("m")
+        .unwrap();
+    assert_eq!(filtered(&f), &["a::medium", "a::small"]);
+}
+
+#[test]
+fn regex_without_prefixes() {
+    let f = FilterSet::new().regex("l$").unwrap();
+    assert_eq!(filtered(&f), &["a::small"]);
+}
+
+#[test]
+fn exclude_overrides_include() {
+    let f = FilterSet::new()
+        .prefix("a::small")
+        .prefix("b::")
+        .regex("a")
+        .unwrap()
+        .exclude("small");
+    assert_eq!(filtered(&f), &["b::large"]);
+}
+
+#[test]
+fn regex_mode() {
+    let f = FilterSet::new()
+        .name(FilterMode::Regex, "::(small|large)$")
+        .unwrap();
+    assert_eq!(filtered(&f), &["b::large", "a::small"]);
+
+    // Prefix mode does not interpret patterns
+    let f = FilterSet::new()
+        .name(FilterMode::Prefix, "::small")
+        .unwrap();
+    assert!(filtered(&f).is_empty());
+
+    assert!(FilterSet::new().name(FilterMode::Regex, "(").is_err());
+}
+
+#[test]
+fn exclude() {
+    let f = FilterSet::new()
+        .prefix("a::")
+        .exclude("tiny")
+        .exclude("med");
+    assert_eq!(filtered(&f), &["a::small"]);
+}
+
+#[test]
+fn addr_range() {
+    let mut r = sized();
+    for (i, f) in r.functions.iter_mut().enumerate() {
+        f.addr = 0x1000 + 0x100 * i as u64;
+    }
+
+    // Bounds are inclusive
+    let f = FilterSet::new()
+        .min_addr(parse_u64("0x1100").unwrap())
+        .max_addr(parse_u64("0x1200").unwrap());
+    r.retain(|func| f.matches(func));
+
+    assert_eq!(names(&r), ["a::small", "a::medium"]);
+
+    assert_eq!(parse_u64("4096"), Some(0x1000));
+    assert_eq!(parse_u64("0x"), None);
+}
+
+#[test]
+fn iterate() {
+    let r = sized();
+
+    let mut total = 0;
+    for f in &r {
+        total += f.stack;
+    }
+    assert_eq!(total, r.summary().total_stack);
+    assert_eq!(r.filter_by(|f| f.name.starts_with("a::")).count(), 3);
+}
+
+#[test]
+fn listing_filters() {
+    let basic = fixture("basic.elf");
+
+    // Listings share the table filters
+    for mode in [
+        &["--search", "big"][..],
+        &["--format", "csv"],
+        &["--by-crate"],
+    ] {
+        let out = wts(&[mode, &["--filter", "sm", "--min-size", "0", &basic]].concat());
+        assert!(out.status.success());
+        let listing = String::from_utf8(out.stdout).unwrap();
+        assert!(
+            !listing.contains("big") && !listing.contains("186"),
+            "{}",
+            listing
+        );
+    }
+
+    // While the stack budget applies to every mode, prior to filtering
+    for mode in [
+        &["--fingerprint"][..],
+        &["--search", "small"],
+        &["--filter", "sm"],
+    ] {
+        let out = wts(&[mode, &["--max-stack", "100", &basic]].concat());
+        assert!(!out.status.success());
+    }
+}
+
+#[test]
+fn column_selection() {
+    let basic = fixture("basic.elf");
+    let header = |args: &[&str]| {
+        let out = wts(&[args, &[&basic]].concat());
+        assert!(out.status.success());
+        let table = String::from_utf8(out.stdout).unwrap();
+        table
+            .lines()
+            .next()
+            .unwrap()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    // Columns are shown in the order given, including optional columns
+    assert_eq!(header(&["--columns", "name,stack"]), "NAME STACK");
+    assert_eq!(
+        header(&[
+            "--show-ratio",
+            "--show-section",
+            "--columns",
+            "section,name"
+        ]),
+        "SECTION NAME"
+    );
+    assert_eq!(
+        header(&["--cumulative", "--columns", "name,cumulative"]),
+        "NAME CUMULATIVE"
+    );
+
+    // Optional columns must be enabled
+    let out = wts(&["--columns", "ratio", &basic]);
+    assert!(!out.status.success());
+}
@@ -0,0 +1,43 @@
+//! Test fixture with known stack use
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+
+/// Large stack frame
+#[inline(never)]
+#[no_mangle]
+pub extern "C" fn big(n: usize) -> u8 {
+    let mut buf = [0u8; 256];
+    for (i, b) in buf.iter_mut().enumerate() {
+        *b = (i + n) as u8;
+    }
+    black_box(&mut buf)[n % 256]
+}
+
+/// Small stack frame, calling `big`
+#[inline(never)]
+#[no_mangle]
+pub extern "C" fn small(n: usize) -> u8 {
+    big(black_box(n)).wrapping_add(1)
+}
+
+/// Mangled function with a medium frame
+#[inline(never)]
+pub fn medium(n: usize) -> u8 {
+    let mut buf = [0u8; 64];
+    buf[n % 64] = n as u8;
+    black_box(&mut buf)[(n + 1) % 64]
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    let v = small(black_box(3)).wrapping_add(medium(black_box(5)));
+    black_box(v);
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
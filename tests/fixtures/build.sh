@@ -0,0 +1,49 @@
+#!/bin/sh
+# Rebuild test fixtures from `basic.rs`, requires a nightly toolchain.
+#
+# Expected stack sizes in `tests/parse.rs` depend on the compiler version,
+# so update these alongside any fixture rebuild.
+set -e
+cd "$(dirname "$0")"
+
+FLAGS="--edition 2021 -C panic=abort -C opt-level=1 -g"
+LINK="-C link-arg=-nostartfiles -C link-arg=-static"
+
+# Executable with stack sizes and relocations (for call graph analysis)
+rustc +nightly $FLAGS -Z emit-stack-sizes --crate-type bin $LINK -C link-arg=-Wl,--emit-relocs basic.rs -o basic.elf
+
+# Executable with zlib-compressed (SHF_COMPRESSED) debug sections
+objcopy --compress-debug-sections=zlib basic.elf compressed.elf
+
+# Executable with inlined code at a function entry
+rustc +nightly $FLAGS -Z emit-stack-sizes --crate-type bin $LINK inline.rs -o inline.elf
+
+# Executable with an unsized function symbol, for symbol table size fallback
+rustc +nightly $FLAGS -Z emit-stack-sizes --crate-type bin $LINK symsize.rs -o symsize.elf
+
+# Executable with many functions, for parallel parsing tests and benchmarks
+rustc +nightly $FLAGS -Z emit-stack-sizes --crate-type bin $LINK many.rs -o many.elf
+
+# Relocatable object with stack sizes
+rustc +nightly $FLAGS -Z emit-stack-sizes --emit obj basic.rs -o basic.o
+
+# COFF object with stack sizes and unsized symbols, requires `perl` to set symbol types
+objcopy -O pe-x86-64 basic.elf coff.obj
+perl coff-types.pl coff.obj
+
+# Executable without stack sizes
+rustc +nightly $FLAGS --crate-type bin $LINK basic.rs -o nostack.elf
+
+# ARM (Thumb) executable with stack sizes, requires the `thumbv7m-none-eabi` target
+rustc +nightly $FLAGS -Z emit-stack-sizes --crate-type bin --target thumbv7m-none-eabi -C link-arg=-e_start basic.rs -o thumb.elf
+
+# Vendor stack size sections (address, ULEB128 size) for 64 and 32-bit targets, with
+# a multi-byte size and a truncated trailing entry overlapping `big`, requires `llvm-objcopy`
+addr() { nm "$1" | awk -v s="$2" '$3 == s { print $1 }'; }
+perl -e 'print pack("Q<", hex($ARGV[0])), "\xac\x02", pack("Q<", hex($ARGV[1])), "\x08", pack("Q<", hex($ARGV[0])), "\x81"' \
+    "$(addr basic.elf big)" "$(addr basic.elf small)" > vendor.bin
+llvm-objcopy --add-section .vendor_stack=vendor.bin basic.elf vendor.elf
+perl -e 'print pack("L<", hex($ARGV[0])), "\xac\x02", pack("L<", hex($ARGV[0])), "\x81"' \
+    "$(addr thumb.elf big)" > vendor.bin
+llvm-objcopy --add-section .vendor_stack=vendor.bin thumb.elf vendor32.elf
+rm vendor.bin
@@ -0,0 +1,22 @@
+#!/usr/bin/perl
+# Mark COFF symbols in code sections as functions, `objcopy` leaves symbol types unset
+use strict;
+
+open(my $f, "+<:raw", $ARGV[0]) or die "failed to open $ARGV[0]: $!";
+my $d = do { local $/; <$f> };
+
+my ($sections, $symtab, $symbols, $opt) = unpack("x2 v x4 V V v", $d);
+for (my $i = 0; $i < $symbols; $i++) {
+    my $o = $symtab + $i * 18;
+    my ($section, $aux) = unpack("s< x3 C", substr($d, $o + 12, 6));
+
+    # Section characteristics follow the COFF and optional headers
+    if ($section > 0 && $section <= $sections && $aux == 0) {
+        my $flags = unpack("V", substr($d, 20 + $opt + ($section - 1) * 40 + 36, 4));
+        substr($d, $o + 14, 2) = pack("v", 0x20) if $flags & 0x20;
+    }
+    $i += $aux;
+}
+
+seek($f, 0, 0);
+print $f $d;
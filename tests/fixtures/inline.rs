@@ -0,0 +1,29 @@
+//! Test fixture with inlined functions
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+
+/// Helper inlined into `outer`
+#[inline(always)]
+fn scale(n: usize) -> usize {
+    n.wrapping_mul(black_box(3)) + 1
+}
+
+/// Function starting with inlined code
+#[inline(never)]
+#[no_mangle]
+pub extern "C" fn outer(n: usize) -> usize {
+    scale(n)
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    black_box(outer(black_box(3)));
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
@@ -0,0 +1,69 @@
+//! Larger test fixture with many functions, for parallel parsing and benchmarks
+#![no_std]
+#![no_main]
+
+use core::{hint::black_box, mem::MaybeUninit};
+
+/// Stack frame of `N` bytes, instantiated once per size (uninitialised to avoid `memset`)
+#[inline(never)]
+pub fn frame<const N: usize>(n: usize) -> u8 {
+    let mut buf = MaybeUninit::<[u8; N]>::uninit();
+    let p = buf.as_mut_ptr() as *mut u8;
+    unsafe {
+        p.add(n % N).write(n as u8);
+        black_box(&mut buf);
+        p.add(n % N).read()
+    }
+}
+
+/// Call `frame` for each listed size
+macro_rules! frames {
+    ($n:expr, $($size:literal)*) => {
+        $( black_box(frame::<$size>($n)); )*
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    let n = black_box(3);
+    frames!(n,
+        8 16 24 32 40 48 56 64 72 80 88 96 104 112 120 128
+        136 144 152 160 168 176 184 192 200 208 216 224 232 240 248 256
+        264 272 280 288 296 304 312 320 328 336 344 352 360 368 376 384
+        392 400 408 416 424 432 440 448 456 464 472 480 488 496 504 512
+        520 528 536 544 552 560 568 576 584 592 600 608 616 624 632 640
+        648 656 664 672 680 688 696 704 712 720 728 736 744 752 760 768
+        776 784 792 800 808 816 824 832 840 848 856 864 872 880 888 896
+        904 912 920 928 936 944 952 960 968 976 984 992 1000 1008 1016 1024
+        1032 1040 1048 1056 1064 1072 1080 1088 1096 1104 1112 1120 1128 1136 1144 1152
+        1160 1168 1176 1184 1192 1200 1208 1216 1224 1232 1240 1248 1256 1264 1272 1280
+        1288 1296 1304 1312 1320 1328 1336 1344 1352 1360 1368 1376 1384 1392 1400 1408
+        1416 1424 1432 1440 1448 1456 1464 1472 1480 1488 1496 1504 1512 1520 1528 1536
+        1544 1552 1560 1568 1576 1584 1592 1600 1608 1616 1624 1632 1640 1648 1656 1664
+        1672 1680 1688 1696 1704 1712 1720 1728 1736 1744 1752 1760 1768 1776 1784 1792
+        1800 1808 1816 1824 1832 1840 1848 1856 1864 1872 1880 1888 1896 1904 1912 1920
+        1928 1936 1944 1952 1960 1968 1976 1984 1992 2000 2008 2016 2024 2032 2040 2048
+        2056 2064 2072 2080 2088 2096 2104 2112 2120 2128 2136 2144 2152 2160 2168 2176
+        2184 2192 2200 2208 2216 2224 2232 2240 2248 2256 2264 2272 2280 2288 2296 2304
+        2312 2320 2328 2336 2344 2352 2360 2368 2376 2384 2392 2400 2408 2416 2424 2432
+        2440 2448 2456 2464 2472 2480 2488 2496 2504 2512 2520 2528 2536 2544 2552 2560
+        2568 2576 2584 2592 2600 2608 2616 2624 2632 2640 2648 2656 2664 2672 2680 2688
+        2696 2704 2712 2720 2728 2736 2744 2752 2760 2768 2776 2784 2792 2800 2808 2816
+        2824 2832 2840 2848 2856 2864 2872 2880 2888 2896 2904 2912 2920 2928 2936 2944
+        2952 2960 2968 2976 2984 2992 3000 3008 3016 3024 3032 3040 3048 3056 3064 3072
+        3080 3088 3096 3104 3112 3120 3128 3136 3144 3152 3160 3168 3176 3184 3192 3200
+        3208 3216 3224 3232 3240 3248 3256 3264 3272 3280 3288 3296 3304 3312 3320 3328
+        3336 3344 3352 3360 3368 3376 3384 3392 3400 3408 3416 3424 3432 3440 3448 3456
+        3464 3472 3480 3488 3496 3504 3512 3520 3528 3536 3544 3552 3560 3568 3576 3584
+        3592 3600 3608 3616 3624 3632 3640 3648 3656 3664 3672 3680 3688 3696 3704 3712
+        3720 3728 3736 3744 3752 3760 3768 3776 3784 3792 3800 3808 3816 3824 3832 3840
+        3848 3856 3864 3872 3880 3888 3896 3904 3912 3920 3928 3936 3944 3952 3960 3968
+        3976 3984 3992 4000 4008 4016 4024 4032 4040 4048 4056 4064 4072 4080 4088 4096
+    );
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
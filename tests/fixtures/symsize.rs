@@ -0,0 +1,34 @@
+//! Test fixture with an unsized function symbol, aliased by a sized symbol
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+
+// `stack-sizes` takes the size of the first symbol at an address, here `nosize`
+core::arch::global_asm!(
+    ".text",
+    ".globl nosize",
+    ".type nosize, @function",
+    ".globl sized",
+    ".type sized, @function",
+    "nosize:",
+    "sized:",
+    "    lea rax, [rdi + 1]",
+    "    ret",
+    ".size sized, . - sized",
+);
+
+extern "C" {
+    fn nosize(n: usize) -> usize;
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    black_box(unsafe { nosize(black_box(3)) });
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
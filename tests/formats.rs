@@ -0,0 +1,142 @@
+//! Tests for report formats
+//!
+//! Report formats require the `serde` feature
+#![cfg(feature = "serde")]
+
+use whatthestack::*;
+
+/// Build a small report
+fn report() -> Report {
+    let f = |addr, name: &str, text, stack| Function {
+        addr,
+        name: name.to_string(),
+        text,
+        stack,
+        ..Default::default()
+    };
+
+    Report {
+        functions: vec![f(0x1000, "a::big", 200, 128), f(0x1100, "a::small", 20, 8)],
+        ..Default::default()
+    }
+}
+
+/// Temporary file path for round-trip tests
+fn temp(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("wts-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name).to_string_lossy().to_string()
+}
+
+#[test]
+fn csv_round_trip() {
+    let r = report();
+    let f = temp("report.csv");
+
+    r.save_as(&f, OutputFormat::Csv, false).unwrap();
+    assert_eq!(Report::load(&f).unwrap(), r);
+}
+
+#[test]
+fn csv_diff_columns() {
+    let r = report();
+    let mut prev = report();
+    prev.functions[0].stack = 100;
+    prev.functions.pop();
+
+    let csv = r.to_csv(Some(&prev)).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "addr,text,stack,name,source,text_diff,stack_diff,change"
+    );
+    assert_eq!(lines[1], "0x00001000,200,128,a::big,,0,28,changed");
+
+    // Added functions count from zero, as in the table
+    assert_eq!(lines[2], "0x00001100,20,8,a::small,,20,8,added");
+
+    // Diff columns are ignored when loading
+    let f = temp("diff.csv");
+    std::fs::write(&f, &csv).unwrap();
+    assert_eq!(Report::load(&f).unwrap(), r);
+
+    // Removed functions have zero current sizes
+    let csv = prev.to_csv(Some(&r)).unwrap();
+    assert_eq!(
+        csv.lines().nth(2),
+        Some("0x00001100,0,0,a::small,,-20,-8,removed")
+    );
+}
+
+#[test]
+fn serde_round_trips() {
+    let mut r = report();
+    r.functions[0].source = "src/lib.rs:10".to_string();
+    r.functions[0].calls = vec!["a::small".to_string()];
+
+    for name in ["report.json", "report.toml", "report.yaml", "report.yml"] {
+        let f = temp(name);
+        r.save(&f, false).unwrap();
+        assert_eq!(Report::load(&f).unwrap(), r, "round trip via {}", name);
+    }
+
+    // Unknown extensions default to JSON
+    let f = temp("report.baseline");
+    r.save(&f, false).unwrap();
+    assert!(std::fs::read_to_string(&f).unwrap().starts_with('{'));
+}
+
+#[test]
+fn legacy_json_without_undefined() {
+    let f = temp("legacy.json");
+    std::fs::write(
+        &f,
+        r#"{"functions":[{"addr":0,"name":"a","text":4,"stack":8}]}"#,
+    )
+    .unwrap();
+
+    let r = Report::load(&f).unwrap();
+    assert_eq!(r.functions.len(), 1);
+    assert!(r.undefined.is_empty());
+}
+
+#[test]
+fn report_versions() {
+    // Unversioned reports load as v1
+    let f = temp("unversioned.json");
+    std::fs::write(&f, r#"{"functions":[]}"#).unwrap();
+    assert_eq!(Report::load(&f).unwrap().version, 1);
+
+    // Saved reports are stamped with the current version
+    let f = temp("versioned.json");
+    report().save(&f, false).unwrap();
+    assert!(std::fs::read_to_string(&f)
+        .unwrap()
+        .contains(&format!("\"version\": {}", REPORT_VERSION)));
+
+    // Newer versions are rejected
+    let f = temp("future.json");
+    std::fs::write(&f, r#"{"version":999,"functions":[]}"#).unwrap();
+    assert!(Report::load(&f).is_err());
+}
+
+#[test]
+fn address_map() {
+    let mut r = report();
+    r.functions.reverse();
+    r.functions.push(Function {
+        addr: 0x1000,
+        name: "a::alias".to_string(),
+        ..Default::default()
+    });
+
+    // Ordered by address, aliases keep the last name
+    let m: Vec<_> = r.address_map().into_iter().collect();
+    assert_eq!(
+        m,
+        [
+            (0x1000, "a::alias".to_string()),
+            (0x1100, "a::small".to_string())
+        ]
+    );
+}
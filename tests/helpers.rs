@@ -0,0 +1,199 @@
+//! Tests for name helpers
+
+use whatthestack::*;
+
+#[test]
+fn strip_generics_nested() {
+    assert_eq!(
+        strip_generics("alloc::vec::Vec<Option<u8>>::push"),
+        "alloc::vec::Vec::push"
+    );
+    assert_eq!(
+        strip_generics("<alloc::vec::Vec<u8> as core::ops::Drop>::drop"),
+        "<alloc::vec::Vec as core::ops::Drop>::drop"
+    );
+}
+
+#[test]
+fn strip_generics_turbofish() {
+    assert_eq!(
+        strip_generics("core::mem::drop::<alloc::string::String>"),
+        "core::mem::drop"
+    );
+    assert_eq!(strip_generics("a::call::<fn() -> u8>"), "a::call");
+}
+
+#[test]
+fn abi_detection() {
+    assert_eq!(Abi::from_symbol("memcpy"), Abi::Extern);
+    assert_eq!(Abi::from_symbol("_start"), Abi::Extern);
+    assert_eq!(
+        Abi::from_symbol("_ZN4core3fmt5write17h0123456789abcdefE"),
+        Abi::Rust
+    );
+    assert_eq!(Abi::from_symbol("_RNvCs1234_1a4main"), Abi::Rust);
+
+    // Not valid manglings, left unchanged by demangling
+    assert_eq!(Abi::from_symbol("_ZN"), Abi::Extern);
+    assert_eq!(Abi::from_symbol("_R"), Abi::Extern);
+}
+
+#[test]
+fn simplify_v0_names() {
+    let demangle = |s| format!("{:#}", rustc_demangle::demangle(s));
+
+    // `h::hr::<for<'a> fn(&'a u8) -> u8>`
+    let name = demangle("_RINvCsStvVbv6f6n_1h2hrFG_RL0_hEhEB2_");
+    assert_eq!(simplify_name(&name), "h::hr::<fn(&u8) -> u8>");
+
+    // `h::hr::<&dyn for<'a> core::ops::function::Fn<(&'a u8,), Output = u8>>`
+    let name = demangle(
+        "_RINvCsStvVbv6f6n_1h2hrRDG_INtNtNtCs8NwYtU1Mohg_4core3ops8function2FnTRL0_hEEp6OutputhEL_EB2_",
+    );
+    assert_eq!(
+        simplify_name(&name),
+        "h::hr::<&dyn core::ops::function::Fn<(&u8,), Output = u8>>"
+    );
+
+    // `<g::B<4>>::go`, const generics are already minimal
+    let name = demangle("_RNvMCsaRyNK4lDSkl_1gINtB2_1BKj4_E2goB2_");
+    assert_eq!(simplify_name(&name), "<g::B<4>>::go");
+
+    assert_eq!(simplify_name("a::S<'_, T>::new::<'a>"), "a::S<T>::new");
+    assert_eq!(simplify_name("b::<4usize, 'x'>"), "b::<4, 'x'>");
+}
+
+#[test]
+fn runtime_symbols() {
+    let cases = [
+        ("core::panicking::panic_fmt", true),
+        ("std::panicking::begin_panic_handler", true),
+        ("compiler_builtins::mem::memcpy", true),
+        ("__aeabi_uldivmod", true),
+        ("__udivdi3", true),
+        ("memcpy", true),
+        // Only panic machinery is excluded from the standard library
+        ("core::fmt::write", false),
+        ("alloc::vec::Vec<T>::push", false),
+        ("std::io::stdio::_print", false),
+        // User crates never match, including names containing runtime prefixes
+        ("app::core::panicking::handler", false),
+        ("app::memcpy", false),
+        ("memcpy_fast", false),
+    ];
+
+    for (name, expected) in cases {
+        assert_eq!(is_runtime(name), expected, "runtime check for {}", name);
+    }
+}
+
+#[test]
+fn fuzzy_scores() {
+    assert!(fuzzy_score("smal", "a::small").is_some());
+    assert!(fuzzy_score("xyz", "a::small").is_none());
+
+    // Segment starts score above mid-word matches
+    assert!(fuzzy_score("big", "a::big") > fuzzy_score("big", "a::abigail"));
+}
+
+#[test]
+fn compress_names() {
+    let cases = [
+        (
+            "<core::future::from_generator::GenFuture<T> as core::future::Future>::poll",
+            "GenFuture::poll",
+        ),
+        (
+            "<alloc::vec::Vec<T,A> as core::ops::drop::Drop>::drop",
+            "Vec::drop",
+        ),
+        ("<&T as core::fmt::Debug>::fmt", "&T::fmt"),
+        (
+            "embassy_executor::raw::TaskStorage<F>::poll",
+            "TaskStorage::poll",
+        ),
+        (
+            "app::sensor::read::{{closure}}::{{closure}}",
+            "read::{{closure}}",
+        ),
+        (
+            "<app::Sensor as app::Driver>::read::{closure#0}",
+            "Sensor::read::{{closure}}",
+        ),
+        ("core::fmt::write", "core::fmt::write"),
+        ("_start", "_start"),
+    ];
+
+    for (name, expected) in cases {
+        assert_eq!(compress_name(name), expected, "compressing {}", name);
+    }
+}
+
+#[test]
+fn module_paths() {
+    assert_eq!(module_path("app::sensor::read"), ["app", "sensor", "read"]);
+    assert_eq!(
+        module_path("<app::Sensor<T> as core::fmt::Debug>::fmt"),
+        ["app", "Sensor", "fmt"]
+    );
+    assert_eq!(module_path("_start"), [UNKNOWN_CRATE, "_start"]);
+}
+
+#[test]
+fn human_sizes() {
+    assert_eq!(human_size(0), "0");
+    assert_eq!(human_size(1023), "1023");
+    assert_eq!(human_size(1024), "1.0 KiB");
+    assert_eq!(human_size(1234), "1.2 KiB");
+    assert_eq!(human_size(3 * 1024 * 1024 / 2), "1.5 MiB");
+}
+
+#[test]
+fn normalize_names() {
+    let cases = [
+        (
+            "<alloc::vec::Vec<T,A> as core::ops::drop::Drop>::drop::h5c7e8f4a2b1d3e6f",
+            "<alloc::vec::Vec<T,A> as core::ops::drop::Drop>::drop",
+        ),
+        (
+            "core::ptr::drop_in_place<std::rt::lang_start<()>::{{closure}}>::h0123456789abcdef",
+            "core::ptr::drop_in_place<std::rt::lang_start<()>::{{closure}}>",
+        ),
+        (
+            "std::rt::lang_start::<()>::{closure#0}",
+            "std::rt::lang_start::<()>::{{closure}}",
+        ),
+        (
+            "app::main::{closure#3}::{closure#12}",
+            "app::main::{{closure}}::{{closure}}",
+        ),
+        ("core::fmt::write.llvm.1234567890", "core::fmt::write"),
+        ("app::hash::h_table", "app::hash::h_table"),
+    ];
+
+    for (name, expected) in cases {
+        assert_eq!(normalize_name(name), expected, "normalising {}", name);
+    }
+}
+
+#[test]
+fn truncate_paths() {
+    let path = "/home/user/project/src/module/file.rs:42";
+
+    // Paths within the width are unchanged
+    assert_eq!(truncate_middle(path, 80), path);
+    assert_eq!(truncate_middle(path, path.len()), path);
+
+    // The file name and line are kept, filling with the start of the path
+    assert_eq!(truncate_middle(path, 24), "/home/user/p…/file.rs:42");
+    assert_eq!(truncate_middle(path, 24).chars().count(), 24);
+
+    // Even where these exceed the width
+    assert_eq!(truncate_middle(path, 4), "…/file.rs:42");
+
+    // Non-path sources are never truncated
+    assert_eq!(truncate_middle("<unknown>:0", 4), "<unknown>:0");
+
+    // Widths count characters, not bytes
+    assert_eq!(truncate_middle("/äöü/äöü/x.rs:1", 12), "/äöü…/x.rs:1");
+}
@@ -0,0 +1,53 @@
+//! Tests for per-function stack limits
+
+use whatthestack::*;
+
+#[test]
+fn parse_limits() {
+    let l = Limits::parse(
+        "# Budgets\n\napp::main = 1024\n  \"<T as Iterator<Item = u8>>::next\" = 64  \nsmall=8\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        l.entries.into_iter().collect::<Vec<_>>(),
+        [
+            ("<T as Iterator<Item = u8>>::next".to_string(), 64),
+            ("app::main".to_string(), 1024),
+            ("small".to_string(), 8),
+        ]
+    );
+
+    let e = Limits::parse("app::main\n").unwrap_err().to_string();
+    assert!(e.contains("line 1"), "{}", e);
+    let e = Limits::parse("# ok\napp::main = big\n")
+        .unwrap_err()
+        .to_string();
+    assert!(e.contains("line 2: invalid stack limit"), "{}", e);
+}
+
+#[test]
+fn check_limits() {
+    let f = |name: &str, stack| Function {
+        name: name.to_string(),
+        stack,
+        ..Default::default()
+    };
+    let r = Report {
+        functions: vec![f("a::big", 256), f("a::exact", 64), f("a::unlisted", 4096)],
+        ..Default::default()
+    };
+    let l = Limits::parse("a::big = 128\na::exact = 64\na::gone = 16").unwrap();
+
+    // Limits are inclusive and unlisted functions are ignored
+    let c = l.check(&r);
+    assert_eq!(c.compliant, 1);
+    assert_eq!(
+        c.violations,
+        [LimitViolation {
+            function: f("a::big", 256),
+            limit: 128,
+        }]
+    );
+    assert_eq!(c.missing, ["a::gone"]);
+}
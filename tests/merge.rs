@@ -0,0 +1,103 @@
+//! Tests for merging reports
+
+mod common;
+
+use common::{fixture, report};
+use whatthestack::*;
+
+/// Merge two units sharing `a::inline`, returning its `(text, stack)`
+fn merged(text: MergePolicy, stack: MergePolicy) -> (u64, u64) {
+    let a = report(&[("a::inline", 32, 16), ("a::first", 8, 8)]);
+    let b = report(&[("a::inline", 64, 48), ("b::second", 8, 8)]);
+
+    let r = Report::merge(vec![a, b], text, stack);
+    assert_eq!(r.functions.len(), 3);
+
+    let f = r.find("a::inline").unwrap();
+    (f.text, f.stack)
+}
+
+#[test]
+fn merge_sum() {
+    assert_eq!(merged(MergePolicy::Sum, MergePolicy::Sum), (96, 64));
+}
+
+#[test]
+fn merge_max() {
+    assert_eq!(merged(MergePolicy::Max, MergePolicy::Max), (64, 48));
+}
+
+#[test]
+fn merge_first() {
+    assert_eq!(merged(MergePolicy::First, MergePolicy::First), (32, 16));
+
+    // Text and stack policies apply independently
+    assert_eq!(merged(MergePolicy::First, MergePolicy::Max), (32, 48));
+}
+
+#[test]
+fn merge_stack_usage() {
+    let elf = Report::parse(&fixture("basic.elf"), Mode::Elf, false).unwrap();
+    let su = Report::parse_su_str(
+        "basic.rs:10:4:big\t200\tstatic\nbasic.rs:27:4:basic::medium\t40\tstatic\n",
+    )
+    .unwrap();
+
+    // Text and addresses come from the ELF regardless of order, stack from the first source with one
+    for (reports, stack) in [(vec![su.clone(), elf.clone()], 200), (vec![elf, su], 152)] {
+        let r = Report::merge(reports, MergePolicy::First, MergePolicy::First);
+
+        let big = r.find("big").unwrap();
+        assert_eq!(big.text, 186);
+        assert_ne!(big.addr, 0);
+        assert_eq!(big.stack, stack);
+
+        // No stack information in the ELF
+        assert_eq!(r.find("basic::medium").unwrap().stack, 40);
+    }
+}
+
+#[test]
+fn merge_resolves_undefined() {
+    let mut a = report(&[("a::big", 200, 128)]);
+    a.undefined = vec!["b::helper".to_string(), "memcpy".to_string()];
+    let b = Report {
+        functions: vec![Function {
+            name: "b::helper".to_string(),
+            ..Default::default()
+        }],
+        undefined: vec!["memcpy".to_string()],
+        ..Default::default()
+    };
+
+    let r = Report::concat(vec![a, b]);
+    assert_eq!(r.undefined, ["memcpy"]);
+}
+
+#[test]
+fn dedup_aliases() {
+    let f = |addr, name: &str, text, stack| Function {
+        addr,
+        name: name.to_string(),
+        text,
+        stack,
+        section: ".text".to_string(),
+        ..Default::default()
+    };
+    let mut r = Report {
+        functions: vec![
+            f(0x10, "memcpy", 64, 16),
+            f(0x10, "__aeabi_memcpy", 64, 16),
+            f(0x50, "a::other", 32, 8),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(r.summary().total_stack, 40);
+
+    r.dedup();
+    assert_eq!(r.functions.len(), 2);
+    assert_eq!(r.functions[0].name, "memcpy");
+    assert_eq!(r.functions[0].aliases, ["__aeabi_memcpy"]);
+    assert_eq!(r.summary().total_stack, 24);
+    assert_eq!(r.summary().total_text, 96);
+}
@@ -0,0 +1,393 @@
+//! End-to-end parsing tests against fixture binaries, see `fixtures/build.sh`
+
+mod common;
+
+use common::fixture;
+use whatthestack::*;
+
+#[test]
+fn parse_elf() {
+    let r = Report::parse(&fixture("basic.elf"), Mode::Elf, false).unwrap();
+
+    let big = r.find("big").unwrap();
+    assert_eq!(big.stack, 152);
+    assert_eq!(big.text, 186);
+    assert_eq!(big.abi, Abi::Extern);
+
+    assert_eq!(r.find("small").unwrap().stack, 8);
+    assert_eq!(r.find("_start").unwrap().stack, 24);
+
+    let medium = r.find("basic::medium").unwrap();
+    assert_eq!(medium.abi, Abi::Rust);
+    assert!(medium.mangled.starts_with("_R") || medium.mangled.starts_with("_ZN"));
+}
+
+#[test]
+fn map_source() {
+    let r = Report::parse(&fixture("basic.elf"), Mode::Elf, true).unwrap();
+
+    assert!(r.find("big").unwrap().source.ends_with("basic.rs:10"));
+    assert!(r.find("small").unwrap().source.ends_with("basic.rs:21"));
+}
+
+#[test]
+fn map_source_compressed() {
+    let r = Report::parse(&fixture("compressed.elf"), Mode::Elf, true).unwrap();
+
+    assert!(r.find("big").unwrap().source.ends_with("basic.rs:10"));
+    assert!(r.find("small").unwrap().source.ends_with("basic.rs:21"));
+}
+
+#[test]
+fn parse_calls() {
+    let r = Report::parse(&fixture("basic.elf"), Mode::Elf, false).unwrap();
+
+    assert_eq!(r.find("small").unwrap().calls, &["big"]);
+
+    let (frames, recursive) = r.worst_path("_start").unwrap();
+    let names: Vec<_> = frames.iter().map(|f| f.function.name.as_str()).collect();
+    assert_eq!(names, &["_start", "small", "big"]);
+    assert_eq!(frames.last().unwrap().cumulative, 24 + 8 + 152);
+    assert!(!recursive);
+}
+
+#[test]
+fn max_depth() {
+    let r = Report::parse(&fixture("basic.elf"), Mode::Elf, false).unwrap();
+
+    let depths: std::collections::HashMap<_, _> = r
+        .max_depth()
+        .into_iter()
+        .map(|(f, d)| (f.name, d))
+        .collect();
+
+    assert_eq!(depths["big"], 152);
+    assert_eq!(depths["small"], 8 + 152);
+    assert_eq!(depths["_start"], 24 + 8 + 152);
+}
+
+#[test]
+fn parse_object() {
+    // Relocatable files are detected and parsed in object mode
+    let r = Report::parse(&fixture("basic.o"), Mode::Elf, false).unwrap();
+
+    let big = r.find("big").unwrap();
+    assert_eq!(big.stack, 152);
+    assert_eq!(big.section, ".text.big");
+}
+
+#[test]
+fn parse_object_mode() {
+    let r = Report::parse(&fixture("basic.o"), Mode::Object, false).unwrap();
+
+    // Each function is emitted in its own section, so addresses are offsets from
+    // the section start rather than linked virtual addresses
+    for name in ["big", "small", "_start", "basic::medium"] {
+        let f = r.find(name).unwrap();
+        assert_eq!(f.addr, 0, "{}", name);
+        assert!(f.section.starts_with(".text."), "{}", name);
+    }
+
+    assert_eq!(r.find("small").unwrap().stack, 8);
+    assert_eq!(r.find("small").unwrap().calls, &["big"]);
+}
+
+#[test]
+fn parse_without_stack_sizes() {
+    let e = Report::parse(&fixture("nostack.elf"), Mode::Elf, false).unwrap_err();
+
+    // Missing sections point at the build configuration
+    assert!(e.to_string().contains("emit-stack-sizes"), "{}", e);
+}
+
+#[test]
+fn parse_stack_section() {
+    // 64-bit addresses with multi-byte sizes, the truncated trailing entry is skipped
+    let opts = ParseOptions::new().stack_section(".vendor_stack");
+    let r = Report::parse_with(&fixture("vendor.elf"), &opts).unwrap();
+    assert_eq!(r.find("big").unwrap().stack, 300);
+    assert_eq!(r.find("small").unwrap().stack, 8);
+
+    // Unlisted functions keep LLVM stack sizes
+    assert_eq!(r.find("_start").unwrap().stack, 24);
+
+    // 32-bit addresses, including the Thumb bit
+    let r = Report::parse_with(&fixture("vendor32.elf"), &opts).unwrap();
+    assert_eq!(r.find("big").unwrap().stack, 300);
+}
+
+#[test]
+fn parse_coff() {
+    // Non-ELF files require an explicit stack section
+    assert!(Report::parse(&fixture("coff.obj"), Mode::Object, false).is_err());
+
+    let opts = ParseOptions::new().stack_section(".stack_sizes");
+    let r = Report::parse_with(&fixture("coff.obj"), &opts).unwrap();
+    assert_eq!(r.functions.len(), 4);
+
+    // COFF addresses are 64-bit despite the 32-bit file class
+    assert_eq!(r.find("big").unwrap().stack, 152);
+    assert_eq!(r.find("_start").unwrap().stack, 24);
+
+    // Unsized symbols extend to the next symbol, or the section end for the last
+    assert_eq!(r.find("basic::medium").unwrap().text, 64);
+    assert_eq!(r.find("big").unwrap().text, 192);
+    assert_eq!(r.find("small").unwrap().text, 32);
+    assert_eq!(r.find("small").unwrap().section, ".text");
+}
+
+#[test]
+fn parse_symbol_size_fallback() {
+    let r = Report::parse(&fixture("symsize.elf"), Mode::Elf, false).unwrap();
+
+    // Sized from the aliasing symbol
+    let f = r.find("nosize").unwrap();
+    assert_eq!(f.text, 5);
+    assert_eq!(f.aliases, ["sized"]);
+
+    assert_eq!(r.find("_start").unwrap().text, 34);
+}
+
+#[test]
+fn parse_missing_stack_section() {
+    // Missing custom sections warn, falling back to LLVM stack sizes
+    let opts = ParseOptions::new().stack_section(".vendor_stack");
+    let r = Report::parse_with(&fixture("basic.elf"), &opts).unwrap();
+    assert_eq!(r.find("big").unwrap().stack, 152);
+
+    // Without any stack information this is reported as empty, not as a build error
+    let r = Report::parse_with(&fixture("nostack.elf"), &opts).unwrap();
+    assert!(r.functions.iter().all(|f| f.stack == 0));
+}
+
+#[test]
+fn parse_arch_hint() {
+    let opts = ParseOptions::new().arch(Architecture::Arm);
+    let r = Report::parse_with(&fixture("thumb.elf"), &opts).unwrap();
+    assert_eq!(r.find("big").unwrap().stack, 296);
+
+    let e = Report::parse_with(&fixture("basic.elf"), &opts).unwrap_err();
+    assert!(e.to_string().contains("architecture mismatch"), "{}", e);
+}
+
+#[test]
+fn parse_missing_file() {
+    assert!(Report::parse(&fixture("missing.elf"), Mode::Elf, false).is_err());
+}
+
+#[test]
+fn parse_bytes() {
+    let d = std::fs::read(fixture("basic.elf")).unwrap();
+    let a = Report::parse_bytes(&d, Mode::Elf, false).unwrap();
+    let b = Report::parse(&fixture("basic.elf"), Mode::Elf, false).unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn parse_parallel() {
+    let opts = ParseOptions::new().map_source(true);
+
+    for f in ["basic.elf", "many.elf"] {
+        let serial = Report::parse_with(&fixture(f), &opts.clone().parallel(false)).unwrap();
+        let parallel = Report::parse_with(&fixture(f), &opts.clone().parallel(true)).unwrap();
+        assert_eq!(serial, parallel);
+        assert!(parallel.functions.iter().any(|f| !f.source.is_empty()));
+    }
+
+    // Larger fixture instantiates one frame per size
+    let r = Report::parse_with(&fixture("many.elf"), &opts).unwrap();
+    assert!(r.functions.len() > 500, "{}", r.functions.len());
+    assert!(r.functions.iter().all(|f| f.source.contains("many.rs")));
+}
+
+#[test]
+fn parse_parallel_workers() {
+    // Source resolution is split between workers, force several regardless of available cores
+    let listing = |threads: &str| {
+        let out = std::process::Command::new(env!("CARGO_BIN_EXE_wts"))
+            .env("RAYON_NUM_THREADS", threads)
+            .args([
+                "--quiet",
+                "--no-cache",
+                "--map-source",
+                "--format",
+                "json",
+                &fixture("many.elf"),
+            ])
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        String::from_utf8(out.stdout).unwrap()
+    };
+
+    let serial = listing("1");
+    assert!(serial.contains(".rs:"), "{}", serial);
+    assert_eq!(serial, listing("4"));
+}
+
+#[test]
+fn inline_frames() {
+    let r = Report::parse(&fixture("basic.elf"), Mode::Elf, true).unwrap();
+
+    // `_start` inlines `black_box` at its entry
+    let inline = &r.find("_start").unwrap().inline;
+    assert!(inline.len() > 1);
+    assert!(inline.last().unwrap().ends_with("(_start)"));
+
+    // `big` has no inlined frames at its entry
+    assert!(r.find("big").unwrap().inline.is_empty());
+}
+
+#[test]
+fn concat_objects() {
+    let a = Report::parse(&fixture("basic.o"), Mode::Object, false).unwrap();
+    let n = a.functions.len();
+
+    let r = Report::concat(vec![a.clone(), a]);
+    assert_eq!(r.functions.len(), 2 * n);
+    assert_eq!(r.duplicates().len(), n);
+    assert!(r.duplicates().values().all(|c| *c == 2));
+}
+
+#[test]
+fn thumb_addresses() {
+    let opts = ParseOptions::new().map_source(true);
+    let r = Report::parse_with(&fixture("thumb.elf"), &opts).unwrap();
+
+    assert_eq!(r.find("big").unwrap().stack, 296);
+    assert!(r.functions.iter().all(|f| f.addr & 1 == 0));
+    assert!(r.find("big").unwrap().source.ends_with("basic.rs:10"));
+
+    // Raw addresses keep the Thumb bit
+    let opts = ParseOptions::new().raw_addresses(true);
+    let r = Report::parse_with(&fixture("thumb.elf"), &opts).unwrap();
+    assert_eq!(r.find("big").unwrap().addr & 1, 1);
+}
+
+#[test]
+fn raw_names() {
+    let r = Report::parse(&fixture("basic.elf"), Mode::Elf, false).unwrap();
+    let medium = r.find("basic::medium").unwrap();
+
+    let opts = ParseOptions::new().raw_names(true);
+    let raw = Report::parse_with(&fixture("basic.elf"), &opts).unwrap();
+
+    // Raw names match the symbol table, unmangled names are unchanged
+    let f = raw.find(&medium.mangled).unwrap();
+    assert_eq!(f.name, f.mangled);
+    assert_eq!(f.stack, medium.stack);
+    assert!(raw.find("basic::medium").is_none());
+    assert!(raw.find("big").is_some());
+}
+
+#[test]
+fn parse_stack_usage() {
+    let su = "src/main.c:12:5:main\t32\tstatic\n\
+              src/util.c:40:6:fill_buffer\t128\tdynamic\n\
+              lib/ns.cpp:7:10:int ns::Foo::bar(int)\t48\tdynamic,bounded\n\
+              old.c:3:legacy\t16\tstatic\n";
+    let r = Report::parse_su_str(su).unwrap();
+    assert_eq!(r.functions.len(), 4);
+
+    let main = r.find("main").unwrap();
+    assert_eq!((main.stack, main.text, main.addr), (32, 0, 0));
+    assert_eq!(main.source, "src/main.c:12");
+    assert_eq!(
+        r.find("int ns::Foo::bar(int)").unwrap().source,
+        "lib/ns.cpp:7"
+    );
+    assert_eq!(r.find("legacy").unwrap().source, "old.c:3");
+
+    let q = |l: &str| parse_su_line(l).unwrap().1;
+    assert_eq!(q("a.c:1:1:f\t8\tstatic"), StackQualifier::Static);
+    assert_eq!(q("a.c:1:1:f\t8\tdynamic"), StackQualifier::Dynamic);
+    assert_eq!(q("a.c:1:1:f\t8\tdynamic,bounded"), StackQualifier::Bounded);
+
+    assert!(Report::parse_su_str("a.c:1:1:f\t8\tunknown").is_err());
+    assert!(Report::parse_su_str("a.c:1:1:f 8 static").is_err());
+}
+
+#[test]
+fn object_source_mapping() {
+    // Section-relative object addresses must not resolve to shared (wrong) locations
+    let r = Report::parse(&fixture("basic.o"), Mode::Object, true).unwrap();
+
+    assert_eq!(r.functions.len(), 5);
+    assert!(
+        r.functions.iter().all(|f| f.source.is_empty()),
+        "{:?}",
+        r.functions
+    );
+    assert!(r.functions.iter().all(|f| f.inline.is_empty()));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn parse_cached() {
+    let dir = std::env::temp_dir().join(format!("wts-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = dir.to_string_lossy().to_string();
+    let entries = || std::fs::read_dir(&dir).unwrap().count();
+
+    let data = std::fs::read(fixture("basic.elf")).unwrap();
+    let opts = ParseOptions::new();
+
+    // Miss, parsing and writing an entry
+    let r = Report::parse_cached(&data, &opts, &cache).unwrap();
+    assert_eq!(r, Report::parse_bytes_with(&data, &opts).unwrap());
+    assert_eq!(entries(), 1);
+
+    // Hit, returning the cached report
+    let entry = std::fs::read_dir(&dir)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let mut cached = r.clone();
+    cached.functions.truncate(1);
+    cached.save(&entry.to_string_lossy(), false).unwrap();
+    assert_eq!(Report::parse_cached(&data, &opts, &cache).unwrap(), cached);
+    assert_eq!(entries(), 1);
+
+    // Invalidated by changes to the data, options or debug file
+    let thumb = std::fs::read(fixture("thumb.elf")).unwrap();
+    assert_ne!(Report::parse_cached(&thumb, &opts, &cache).unwrap(), cached);
+    assert_eq!(entries(), 2);
+
+    let opts = opts.map_source(true);
+    assert_ne!(Report::parse_cached(&data, &opts, &cache).unwrap(), cached);
+    assert_eq!(entries(), 3);
+
+    let debug = std::env::temp_dir().join(format!("wts-cache-{}.debug", std::process::id()));
+    std::fs::copy(fixture("basic.elf"), &debug).unwrap();
+    let opts = opts.debug_file(&debug.to_string_lossy());
+    Report::parse_cached(&data, &opts, &cache).unwrap();
+    std::fs::copy(fixture("compressed.elf"), &debug).unwrap();
+    Report::parse_cached(&data, &opts, &cache).unwrap();
+    assert_eq!(entries(), 5);
+}
+
+#[test]
+fn symbol_lookup() {
+    let basic = std::fs::read(fixture("basic.elf")).unwrap();
+    let thumb = std::fs::read(fixture("thumb.elf")).unwrap();
+    let r = Report::parse_bytes(&basic, Mode::Elf, false).unwrap();
+    let big = r.find("big").unwrap();
+
+    let mut s = SymbolLookup::new();
+    s.add(&basic).unwrap();
+    assert_eq!(s.symbol_at(big.addr), Some(("big", 0)));
+    assert_eq!(s.symbol_at(big.addr + 4), Some(("big", 4)));
+
+    // Addresses past the end of a sized symbol do not match it
+    assert_ne!(s.symbol_at(big.addr + big.text).map(|s| s.0), Some("big"));
+
+    // Thumb addresses are masked, with earlier files taking precedence
+    let r = Report::parse_bytes(&thumb, Mode::Elf, false).unwrap();
+    let big = r.find("big").unwrap();
+    let mut s = SymbolLookup::new();
+    s.add(&thumb).unwrap();
+    s.add(&basic).unwrap();
+    assert_eq!(s.symbol_at(big.addr + 2), Some(("big", 2)));
+}
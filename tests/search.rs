@@ -0,0 +1,21 @@
+//! Tests for function lookup by name
+
+mod common;
+
+use common::sized;
+use whatthestack::*;
+
+#[test]
+fn find_all() {
+    let mut r = sized();
+    r.functions.push(Function {
+        name: "<a::Sensor<u8> as a::Driver>::read".to_string(),
+        ..Default::default()
+    });
+
+    let names = |n: &str| -> Vec<String> { r.find_all(n).iter().map(|f| f.name.clone()).collect() };
+    assert_eq!(names("a::sm"), ["a::small"]);
+    assert_eq!(names("::large"), ["b::large"]);
+    assert_eq!(names("Sensor::read").len(), 1);
+    assert!(names("missing").is_empty());
+}
@@ -0,0 +1,76 @@
+//! Tests for report sorting
+
+mod common;
+
+use common::{fixture, names, sized};
+use whatthestack::*;
+
+#[test]
+fn sort_by_stack() {
+    let mut r = Report::parse(&fixture("basic.elf"), Mode::Elf, false).unwrap();
+    r.sort(Sort::Stack, Order::Desc);
+
+    assert_eq!(names(&r), &["big", "_start", "small", "basic::medium"]);
+}
+
+#[test]
+fn sort_order() {
+    let mut r = sized();
+    r.sort(Sort::Stack, Order::Asc);
+    assert_eq!(r.functions[0].name, "a::tiny");
+
+    r.sort(Sort::Text, Sort::Text.default_order());
+    assert_eq!(r.functions[0].name, "b::large");
+}
+
+#[test]
+fn sort_ties() {
+    let f = |addr, name: &str, text, stack| Function {
+        addr,
+        name: name.to_string(),
+        text,
+        stack,
+        ..Default::default()
+    };
+    let functions = vec![
+        f(0x30, "c", 8, 16),
+        f(0x10, "a", 4, 16),
+        f(0x20, "b", 8, 16),
+        f(0x40, "d", 2, 32),
+    ];
+
+    // Ties on stack fall back to address, independent of input order
+    for mut functions in [functions.clone(), functions.iter().rev().cloned().collect()] {
+        functions.rotate_left(1);
+        let mut r = Report {
+            functions,
+            ..Default::default()
+        };
+        r.sort(Sort::Stack, Order::Desc);
+        assert_eq!(names(&r), ["d", "a", "b", "c"]);
+    }
+
+    // Secondary keys apply before the tiebreaker
+    let mut r = Report {
+        functions,
+        ..Default::default()
+    };
+    r.sort_keys(&[(Sort::Stack, Order::Desc), (Sort::Text, Order::Desc)]);
+    assert_eq!(names(&r), ["d", "b", "c", "a"]);
+
+    // The tiebreaker is independent of the key order
+    r.sort(Sort::Stack, Order::Asc);
+    assert_eq!(names(&r), ["a", "b", "c", "d"]);
+
+    // Expressions use the same tiebreaker, in either order
+    let e: SortExpr = "stack * 2".parse().unwrap();
+    r.sort_by_expr(&e, Order::Desc);
+    assert_eq!(names(&r), ["d", "a", "b", "c"]);
+    r.sort_by_expr(&e, Order::Asc);
+    assert_eq!(names(&r), ["a", "b", "c", "d"]);
+
+    // Cumulative depths replace stack, falling back to the function stack
+    let depths = [("a".to_string(), 64), ("c".to_string(), 32)].into();
+    r.sort_cumulative(&[(Sort::Stack, Order::Desc)], &depths);
+    assert_eq!(names(&r), ["a", "c", "d", "b"]);
+}
@@ -0,0 +1,57 @@
+//! Tests for source file queries
+
+use whatthestack::*;
+
+/// Build a report from `(name, stack, source)` entries
+fn report() -> Report {
+    let f = |name: &str, stack, source: &str| Function {
+        name: name.to_string(),
+        stack,
+        source: source.to_string(),
+        ..Default::default()
+    };
+
+    Report {
+        functions: vec![
+            f("a::one", 16, "/build/app/src/foo.rs:10"),
+            f("a::two", 32, "/build/app/src/foo.rs:42"),
+            f("a::three", 64, "/build/app/src/bar/foo.rs:3"),
+            f("a::four", 8, "/build/app/src/myfoo.rs:1"),
+            f("a::unmapped", 128, ""),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn functions_in_file() {
+    let r = report();
+    let names = |file| -> Vec<&str> {
+        r.functions_in_file(file)
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect()
+    };
+
+    // Suffixes match on path components only
+    assert_eq!(names("src/foo.rs"), ["a::one", "a::two"]);
+    assert_eq!(names("/build/app/src/foo.rs"), ["a::one", "a::two"]);
+    assert_eq!(names("foo.rs"), ["a::one", "a::two", "a::three"]);
+    assert!(names("oo.rs").is_empty());
+    assert!(names("").is_empty());
+}
+
+#[test]
+fn stack_by_file() {
+    let files: Vec<_> = report().stack_by_file().into_iter().collect();
+
+    // Unmapped functions are omitted
+    assert_eq!(
+        files,
+        [
+            ("/build/app/src/bar/foo.rs".to_string(), 64),
+            ("/build/app/src/foo.rs".to_string(), 16 + 32),
+            ("/build/app/src/myfoo.rs".to_string(), 8),
+        ]
+    );
+}
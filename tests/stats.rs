@@ -0,0 +1,46 @@
+//! Tests for report summaries and statistics
+
+mod common;
+
+use common::{fixture, sized, wts};
+use whatthestack::*;
+
+#[test]
+fn summary() {
+    let mut r = sized();
+    r.retain(|f| f.stack > 0);
+
+    let s = r.summary();
+    assert_eq!(s.function_count, 3);
+    assert_eq!(s.total_text, 32 + 64 + 512);
+    assert_eq!(s.total_stack, 16 + 64 + 256);
+    assert_eq!(s.max_stack, 256);
+
+    assert_eq!(Report::default().summary(), Summary::default());
+}
+
+#[test]
+fn stats() {
+    let s = sized().stats();
+    assert_eq!(s.mean, (16 + 64 + 256) as f64 / 4.0);
+    assert_eq!(s.median, 16);
+    assert_eq!(s.p95, 256);
+    assert_eq!(s.max, 256);
+
+    assert_eq!(Report::default().stats(), Stats::default());
+}
+
+#[test]
+fn summary_row() {
+    // Totals follow `--human`, including the max stack
+    let out = wts(&["--summary", "--human", "-n", "2", &fixture("many.elf")]);
+    assert!(out.status.success());
+    let table = String::from_utf8(out.stdout).unwrap();
+    let total = table.lines().last().unwrap();
+    assert!(total.trim_start().starts_with("TOTAL"), "{}", table);
+    assert!(
+        total.contains("2 functions, max stack 3.9 KiB"),
+        "{}",
+        table
+    );
+}
@@ -0,0 +1,65 @@
+//! Tests for per-task stack budgets
+
+mod common;
+
+use common::call_report as report;
+use whatthestack::*;
+
+#[test]
+fn parse_tasks() {
+    let t =
+        Tasks::parse("# RTOS tasks\n\nnet:2048 = app::net::, smoltcp::\n  idle = app::idle ,\n")
+            .unwrap();
+
+    assert_eq!(
+        t.tasks,
+        [
+            Task {
+                name: "net".to_string(),
+                prefixes: vec!["app::net::".to_string(), "smoltcp::".to_string()],
+                limit: Some(2048),
+            },
+            Task {
+                name: "idle".to_string(),
+                prefixes: vec!["app::idle".to_string()],
+                limit: None,
+            },
+        ]
+    );
+
+    assert!(Tasks::parse("net app::net::").is_err());
+    assert!(Tasks::parse("net:big = app::net::").is_err());
+}
+
+#[test]
+fn summarise_tasks() {
+    let r = report(&[
+        ("app::net::poll", 64, &["app::net::rx", "lib::parse"]),
+        ("app::net::rx", 128, &[]),
+        ("lib::parse", 256, &[]),
+        ("app::idle", 16, &[]),
+        ("app::log", 512, &[]),
+    ]);
+    let t = Tasks::parse("net:256 = app::net::\nidle = app::idle\nnone = app::missing").unwrap();
+
+    let s = t.summarise(&r);
+    let summary = |name, functions, worst, function: Option<&str>, limit| TaskSummary {
+        name: String::from(name),
+        functions,
+        worst,
+        worst_function: function.map(String::from),
+        limit,
+    };
+
+    // Worst case includes callees outside of the task prefixes
+    assert_eq!(
+        s,
+        [
+            summary("net", 2, 64 + 256, Some("app::net::poll"), Some(256)),
+            summary("idle", 1, 16, Some("app::idle"), None),
+            summary("none", 0, 0, None, None),
+        ]
+    );
+    assert!(s[0].over_limit());
+    assert!(!s[1].over_limit());
+}